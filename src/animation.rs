@@ -1,79 +1,512 @@
+use crate::format;
 use crate::human;
+use crate::loader::LoadError;
+use crate::skeleton::{Pose, Skeleton};
 use human::Point;
+use std::collections::HashMap;
+use std::rc::Rc;
+#[cfg(feature = "web")]
 use wasm_bindgen::JsCast;
 
 #[allow(unused_imports)]
 use crate::trace;
 
-/// Milliseconds in between animation positions.
-const ANIMATION_POSITION_INTERVAL: f64 = 200.0;
+/// Milliseconds in between animation positions when no timing is recorded.
+/// Files saved before keyframes carried their own time are spaced by this.
+pub const ANIMATION_POSITION_INTERVAL: f64 = 200.0;
+/// Closest two keyframes can be retimed to each other, in milliseconds
+pub const MIN_KEYFRAME_GAP: f64 = 1.0;
 
-#[derive(Debug)]
+/// Registry of animations by name
+#[derive(Debug, Default)]
 pub struct AnimationsData {
-    pub walking: Animation,
+    animations: HashMap<String, Rc<Animation>>,
 }
 
 impl AnimationsData {
-    /// Include animations from files as parsed bytes.
-    pub fn load_animations() -> AnimationsData {
-        AnimationsData {
-            walking: serde_cbor::from_slice(include_bytes!("../data/output.cbor")).unwrap(),
+    /// Parse the walk cycle included in the binary, for when loading animations fails.
+    pub fn embedded() -> Result<AnimationsData, LoadError> {
+        let mut walking: Animation = serde_cbor::from_slice(include_bytes!("../data/output.cbor"))
+            .map_err(|e| LoadError::Parse("data/output.cbor".to_string(), e.to_string()))?;
+        walking.metadata.name = Some("walking".to_string());
+        walking.playback = PlaybackMode::Loop;
+        walking.skeleton = Skeleton::average(walking.keyframes.iter().map(|k| &k.position));
+
+        // stand still in the first position of the walk
+        let mut idle = Animation::new();
+        idle.metadata.name = Some("idle".to_string());
+        if let Some(first) = walking.keyframes.first() {
+            idle.push(first.position.clone());
         }
+        idle.playback = PlaybackMode::Clamp;
+
+        let mut animations = AnimationsData::default();
+        animations.insert("idle", idle);
+        animations.insert("walking", walking);
+        Ok(animations)
+    }
+    /// Add an animation, replacing any with the same name
+    pub fn insert(&mut self, name: &str, animation: Animation) {
+        self.animations.insert(name.to_string(), Rc::new(animation));
+    }
+    /// Get an animation by name
+    pub fn get(&self, name: &str) -> Option<Rc<Animation>> {
+        self.animations.get(name).cloned()
     }
 }
 
-/// Struct containing animation positions
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+/// Generate a download of a file (for development purposes)
+#[cfg(feature = "web")]
+pub fn download(bytes: &[u8], file_name: &str) {
+    let window = web_sys::window().expect("no global `window` exists");
+    let document = window.document().expect("should have a document on window");
+    let array = js_sys::Array::new();
+    array.push(&js_sys::Uint8Array::from(bytes));
+    let blob = web_sys::Blob::new_with_u8_array_sequence(&array).expect("unable to create blob");
+
+    let a = document
+        .create_element("a")
+        .expect("should have created an element");
+    let a = a
+        .dyn_ref::<web_sys::HtmlAnchorElement>()
+        .expect("should have created an anchor element");
+    a.set_href(&web_sys::Url::create_object_url_with_blob(&blob).expect("unable to create url"));
+    let body = document.body().expect("should have a body");
+    a.set_download(file_name);
+    body.append_child(a)
+        .expect("should have appended the anchor element to the body");
+    a.click();
+    body.remove_child(a)
+        .expect("should have removed the anchor element from the body");
+    trace!("downloaded {}", file_name);
+}
+
+/// Struct containing animation keyframes, sorted by time.
+/// Saved in the versioned [`format::AnimationFile`] layout.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(
+    try_from = "format::SerializedAnimation",
+    into = "format::AnimationFile"
+)]
 pub struct Animation {
-    pub positions: Vec<AnimationPosition>,
+    pub keyframes: Vec<Keyframe>,
+    pub playback: PlaybackMode,
+    /// How positions are interpolated between keyframes that do not specify their own
+    pub interpolation: Interpolation,
+    /// Milliseconds taken to interpolate from the last keyframe back to the first when looping
+    pub loop_interval: f64,
+    /// Bone lengths to hold constant during playback.
+    /// When set, keyframes are interpolated by joint angle rather than joint position.
+    pub skeleton: Option<Skeleton>,
+    pub metadata: format::Metadata,
+    /// Frames per second of the footage the animation was traced from
+    pub frame_rate: Option<f64>,
+}
+
+/// What an animation does once its last keyframe has been reached
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+pub enum PlaybackMode {
+    /// Play through once, then end
+    #[default]
+    Once,
+    /// Play through once, then hold the last position
+    Clamp,
+    /// Repeat forever, interpolating from the last keyframe back to the first
+    Loop,
+    /// Alternate between playing forwards and backwards forever
+    PingPong,
+    /// Loop the given number of times, then end
+    LoopTimes(u32),
+}
+
+/// A position and the time (in milliseconds since the start of the animation) it is reached
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct Keyframe {
+    pub time: f64,
+    pub position: AnimationPosition,
+    /// Overrides the animation's interpolation for the segment starting at this keyframe
+    #[serde(default)]
+    pub interpolation: Option<Interpolation>,
+    /// Milliseconds into the footage the position was traced from
+    #[serde(default)]
+    pub reference_time: Option<f64>,
+}
+
+/// Ways of moving between the positions of two keyframes
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+pub enum Interpolation {
+    /// Hold the first position until the next keyframe is reached
+    Step,
+    /// Move at a constant speed
+    #[default]
+    Linear,
+    /// Accelerate out of the first position and decelerate into the next
+    EaseInOut,
+    /// Smooth curve through the keyframes, shaped by the keyframes either side
+    CatmullRom,
+}
+
+/// Cubic ease in and out of an offset between 0 and 1
+fn ease_in_out(offset: f64) -> f64 {
+    if offset < 0.5 {
+        4.0 * offset * offset * offset
+    } else {
+        1.0 - (-2.0 * offset + 2.0).powi(3) / 2.0
+    }
+}
+
+/// A keyframe's time and position, as used when interpolating
+type TimedPosition<'a> = (f64, &'a AnimationPosition);
+
+impl Default for Animation {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Animation {
+    /// An empty animation that plays once
+    pub fn new() -> Self {
+        Animation {
+            keyframes: vec![],
+            playback: PlaybackMode::default(),
+            interpolation: Interpolation::default(),
+            loop_interval: ANIMATION_POSITION_INTERVAL,
+            skeleton: None,
+            metadata: format::Metadata::default(),
+            frame_rate: None,
+        }
+    }
+    /// Time of the last keyframe
+    pub fn duration(&self) -> f64 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+    /// Length of one repetition of the animation, including any return to the first keyframe
+    pub fn cycle(&self) -> f64 {
+        match self.playback {
+            PlaybackMode::Once | PlaybackMode::Clamp => self.duration(),
+            PlaybackMode::Loop | PlaybackMode::LoopTimes(_) => self.duration() + self.loop_interval,
+            PlaybackMode::PingPong => 2.0 * self.duration(),
+        }
+    }
+    /// Like [`Animation::step`], but holds the last keyframe once the animation has ended
+    pub fn position_at(&self, time_step: f64) -> Option<AnimationPosition> {
+        self.step(time_step)
+            .or_else(|| Some(self.keyframes.last()?.position.clone()))
+    }
+    /// Like [`Animation::step`], but with the hip held at the horizontal position of
+    /// the first keyframe, leaving the figure's movement to [`Animation::root_motion`]
+    pub fn step_in_place(&self, time_step: f64) -> Option<AnimationPosition> {
+        let first_hip = self.keyframes.first()?.position.hip;
+        let joints = self.step(time_step)?;
+        let drift = joints.hip.x - first_hip.x;
+        Some(joints.map(|joint| Point {
+            x: joint.x - drift,
+            y: joint.y,
+        }))
+    }
+    /// Horizontal distance the hip has moved from the first keyframe after playing for
+    /// a time, counting every repetition so far. Looping animations keep moving on at
+    /// their average speed while returning to the first keyframe, rather than back.
+    pub fn root_motion(&self, time_step: f64) -> f64 {
+        let duration = self.duration();
+        let time_step = time_step.max(0.0);
+        // distance travelled by a time within the keyframes
+        let travelled = |time: f64| match (self.sample(time), self.keyframes.first()) {
+            (Some(joints), Some(first)) => joints.hip.x - first.position.hip.x,
+            _ => 0.0,
+        };
+        match self.playback {
+            PlaybackMode::Once | PlaybackMode::Clamp => travelled(time_step.min(duration)),
+            PlaybackMode::PingPong => {
+                if duration <= 0.0 {
+                    return 0.0;
+                }
+                let time_step = time_step % (2.0 * duration);
+                travelled(duration - (duration - time_step).abs())
+            }
+            PlaybackMode::Loop | PlaybackMode::LoopTimes(_) => {
+                let cycle = self.cycle();
+                let start = self.keyframes.first().map_or(0.0, |k| k.time);
+                if duration - start <= 0.0 || cycle <= 0.0 {
+                    return 0.0;
+                }
+                let time_step = match self.playback {
+                    PlaybackMode::LoopTimes(times) => {
+                        time_step.min(times as f64 * cycle - self.loop_interval)
+                    }
+                    _ => time_step,
+                };
+                let speed = travelled(duration) / (duration - start);
+                let repetitions = (time_step / cycle).floor();
+                let time_step = time_step - repetitions * cycle;
+                let within = if time_step < start && repetitions == 0.0 {
+                    0.0
+                } else if time_step >= start && time_step <= duration {
+                    travelled(time_step)
+                } else {
+                    // returning to the first keyframe, which may carry on into the next
+                    // repetition until the first keyframe's time
+                    speed * (time_step - start)
+                };
+                repetitions * speed * cycle + within
+            }
+        }
+    }
+    /// Time of the first keyframe shown after a time since the start of the animation,
+    /// or the last one shown before it if not `forward`, following the playback mode
+    pub fn adjacent_keyframe_time(&self, time_step: f64, forward: bool) -> Option<f64> {
+        // a little leeway so stepping from a keyframe does not land on it again
+        const LEEWAY: f64 = 1e-6;
+        let cycle = self.cycle();
+        let looping =
+            !matches!(self.playback, PlaybackMode::Once | PlaybackMode::Clamp) && cycle > 0.0;
+        let mut times: Vec<f64> = self.keyframes.iter().map(|k| k.time).collect();
+        if self.playback == PlaybackMode::PingPong {
+            times.extend(self.keyframes.iter().map(|k| cycle - k.time));
+        }
+        // keyframes of this repetition and the ones either side
+        let (start, repetitions) = if looping {
+            (time_step - time_step.rem_euclid(cycle), -1..=1)
+        } else {
+            (0.0, 0..=0)
+        };
+        let candidates = repetitions
+            .flat_map(|r| times.iter().map(move |t| start + r as f64 * cycle + t))
+            .filter(|t| *t >= 0.0);
+        if forward {
+            candidates
+                .filter(|t| *t > time_step + LEEWAY)
+                .min_by(f64::total_cmp)
+        } else {
+            candidates
+                .filter(|t| *t < time_step - LEEWAY)
+                .max_by(f64::total_cmp)
+        }
+    }
+    /// Append a position one default interval after the last keyframe
+    pub fn push(&mut self, position: AnimationPosition) {
+        self.keyframes.push(Keyframe {
+            time: self.next_keyframe_time(),
+            position,
+            interpolation: None,
+            reference_time: None,
+        });
+    }
+    /// Time a keyframe appended with [`Animation::push`] is given
+    pub fn next_keyframe_time(&self) -> f64 {
+        match self.keyframes.last() {
+            Some(last) => last.time + ANIMATION_POSITION_INTERVAL,
+            None => 0.0,
+        }
+    }
+    /// Insert a keyframe at an index, at the time of the keyframe already there. Keyframe
+    /// times stay where they are, so each later keyframe moves along to the next one's
+    /// time, with one appended at the end
+    pub fn insert_position(&mut self, index: usize, mut keyframe: Keyframe) {
+        let end = self.next_keyframe_time();
+        keyframe.time = self.keyframes.get(index).map_or(end, |k| k.time);
+        self.keyframes.insert(index, keyframe);
+        for i in index + 1..self.keyframes.len() {
+            self.keyframes[i].time = self.keyframes.get(i + 1).map_or(end, |k| k.time);
+        }
+    }
+    /// Remove the keyframe at an index, moving each later keyframe back to the
+    /// previous one's time and dropping the last keyframe time
+    pub fn remove_position(&mut self, index: usize) -> Keyframe {
+        for i in (index + 1..self.keyframes.len()).rev() {
+            self.keyframes[i].time = self.keyframes[i - 1].time;
+        }
+        self.keyframes.remove(index)
+    }
+    /// Move the times of some keyframes by the same amount, as far as they can go without
+    /// passing another keyframe or going before the start. Returns how far they moved
+    pub fn shift_keyframes(&mut self, indices: &[usize], delta: f64) -> f64 {
+        if indices.is_empty() {
+            return 0.0;
+        }
+        let (mut lowest, mut highest) = (f64::NEG_INFINITY, f64::INFINITY);
+        for &index in indices {
+            let time = self.keyframes[index].time;
+            let previous = match index.checked_sub(1) {
+                Some(previous) if indices.contains(&previous) => None,
+                Some(previous) => Some(self.keyframes[previous].time + MIN_KEYFRAME_GAP),
+                None => Some(0.0),
+            };
+            if let Some(previous) = previous {
+                lowest = lowest.max(previous - time);
+            }
+            match self.keyframes.get(index + 1) {
+                Some(_) if indices.contains(&(index + 1)) => {}
+                Some(next) => highest = highest.min(next.time - MIN_KEYFRAME_GAP - time),
+                None => {}
+            }
+        }
+        if lowest > highest {
+            return 0.0;
+        }
+        let delta = delta.clamp(lowest, highest);
+        for &index in indices {
+            self.keyframes[index].time += delta;
+        }
+        delta
+    }
+    /// Exchange the positions of two keyframes, leaving their times in place
+    pub fn swap_positions(&mut self, a: usize, b: usize) {
+        self.keyframes.swap(a, b);
+        let time = self.keyframes[a].time;
+        self.keyframes[a].time = self.keyframes[b].time;
+        self.keyframes[b].time = time;
+    }
     /// Generate a download of the positions stored (for development purposes)
-    pub fn run_download(&self) {
-        let window = web_sys::window().expect("no global `window` exists");
-        let document = window.document().expect("should have a document on window");
-        let byte_vector = serde_cbor::to_vec(&self).expect("unable to serialize object");
-        let array = js_sys::Array::new();
-        array.push(&js_sys::Uint8Array::from(&byte_vector[..]));
-        let blob =
-            web_sys::Blob::new_with_u8_array_sequence(&array).expect("unable to create blob");
-
-        let a = document
-            .create_element("a")
-            .expect("should have created an element");
-        let a = a
-            .dyn_ref::<web_sys::HtmlAnchorElement>()
-            .expect("should have created an anchor element");
-        a.set_href(
-            &web_sys::Url::create_object_url_with_blob(&blob).expect("unable to create url"),
-        );
-        let body = document.body().expect("should have a body");
-        a.set_download("output.cbor");
-        body.append_child(a)
-            .expect("should have appended the anchor element to the body");
-        a.click();
-        body.remove_child(a)
-            .expect("should have removed the anchor element from the body");
-        trace!("downloaded");
-    }
-    /// Get an interpolated position for a time since the start of the animation
-    /// Returns none if the time is out of bounds for the specific animation
+    #[cfg(feature = "web")]
+    pub fn run_download(&self, encoding: format::Encoding) {
+        let byte_vector = encoding.encode(self).expect("unable to serialize object");
+        download(&byte_vector, &format!("output.{}", encoding.extension()));
+    }
+    /// Get an interpolated position for a time since the start of the animation,
+    /// following the animation's playback mode.
+    /// Returns none if the animation has ended (or not yet started)
     pub fn step(&self, time_step: f64) -> Option<AnimationPosition> {
-        let index = (time_step / ANIMATION_POSITION_INTERVAL).floor() as usize;
-        if index >= self.positions.len() {
-            None
+        if !time_step.is_finite() || time_step < 0.0 {
+            return None;
+        }
+        let duration = self.duration();
+        match self.playback {
+            PlaybackMode::Once => self.sample(time_step),
+            PlaybackMode::Clamp => self.sample(time_step.min(duration)),
+            PlaybackMode::Loop => self.sample_looped(time_step),
+            PlaybackMode::PingPong => {
+                if duration <= 0.0 {
+                    return self.sample(0.0);
+                }
+                let time_step = time_step % (2.0 * duration);
+                if time_step > duration {
+                    self.sample(2.0 * duration - time_step)
+                } else {
+                    self.sample(time_step)
+                }
+            }
+            PlaybackMode::LoopTimes(times) => {
+                // the final loop ends on the last keyframe rather than returning to the first
+                let end = times as f64 * (duration + self.loop_interval) - self.loop_interval;
+                if time_step > end {
+                    None
+                } else {
+                    self.sample_looped(time_step)
+                }
+            }
+        }
+    }
+    /// Interpolated position for a time within one loop, including the
+    /// interpolation from the last keyframe back to the first
+    fn sample_looped(&self, time_step: f64) -> Option<AnimationPosition> {
+        let duration = self.duration();
+        let loop_duration = duration + self.loop_interval;
+        if loop_duration <= 0.0 {
+            return self.sample(0.0);
+        }
+        let first = self.keyframes.first()?;
+        let repetition = time_step % loop_duration;
+        // after the first repetition, the time before the first keyframe is still
+        // returning to it from the last
+        let time_step = if time_step >= loop_duration && repetition < first.time {
+            repetition + loop_duration
+        } else {
+            repetition
+        };
+        if time_step <= duration {
+            self.sample(time_step)
         } else {
-            let start = &self.positions.get(index)?;
-            let end = &self.positions.get(index + 1)?;
-            let time_offset = time_step % ANIMATION_POSITION_INTERVAL;
-            let offset_normalized = time_offset / ANIMATION_POSITION_INTERVAL;
-            Some(start.lerp(end, offset_normalized))
+            // the segment wraps around, so its neighbours come from both ends
+            let keyframes = &self.keyframes;
+            let last = keyframes.last()?;
+            let before = &keyframes[keyframes.len().saturating_sub(2)];
+            let after = keyframes.get(1).unwrap_or(first);
+            Some(self.interpolate(
+                last.interpolation,
+                [
+                    (before.time, &before.position),
+                    (last.time, &last.position),
+                    (first.time + loop_duration, &first.position),
+                    (after.time + loop_duration, &after.position),
+                ],
+                time_step,
+            ))
+        }
+    }
+    /// Interpolated position between the keyframes surrounding a time, holding the
+    /// first keyframe until it is reached.
+    /// Returns none if the time is out of bounds for the keyframes
+    fn sample(&self, time_step: f64) -> Option<AnimationPosition> {
+        let first = self.keyframes.first()?;
+        if !time_step.is_finite() || time_step < 0.0 || time_step > self.duration() {
+            return None;
+        }
+        if time_step < first.time {
+            return self.sample(first.time);
+        }
+        // index of the first keyframe after the time step
+        let index = self.keyframes.partition_point(|k| k.time <= time_step);
+        let start = &self.keyframes[index - 1];
+        match self.keyframes.get(index) {
+            Some(end) => {
+                let before = &self.keyframes[(index - 1).saturating_sub(1)];
+                let after = self.keyframes.get(index + 1).unwrap_or(end);
+                Some(self.interpolate(
+                    start.interpolation,
+                    [
+                        (before.time, &before.position),
+                        (start.time, &start.position),
+                        (end.time, &end.position),
+                        (after.time, &after.position),
+                    ],
+                    time_step,
+                ))
+            }
+            None => Some(start.position.clone()),
+        }
+    }
+    /// Position at a time between `start` and `end`, with the keyframes either side
+    /// of the segment shaping splines
+    fn interpolate(
+        &self,
+        interpolation: Option<Interpolation>,
+        [before, start, end, after]: [TimedPosition; 4],
+        time_step: f64,
+    ) -> AnimationPosition {
+        let segment = end.0 - start.0;
+        let offset = (time_step - start.0) / segment;
+        // scale spline tangents by the segment length so uneven spacing does not overshoot
+        let tangent_scale = (segment / (end.0 - before.0), segment / (after.0 - start.0));
+        let interpolation = interpolation.unwrap_or(self.interpolation);
+        if let Some(skeleton) = &self.skeleton {
+            let [before, start, end, after] =
+                [before, start, end, after].map(|(_, p)| Pose::from_position(p));
+            let pose = match interpolation {
+                Interpolation::Step => start,
+                Interpolation::Linear => start.lerp(&end, offset),
+                Interpolation::EaseInOut => start.lerp(&end, ease_in_out(offset)),
+                Interpolation::CatmullRom => {
+                    start.catmull_rom(&before, &end, &after, tangent_scale, offset)
+                }
+            };
+            return pose.to_position(skeleton);
+        }
+        match interpolation {
+            Interpolation::Step => start.1.clone(),
+            Interpolation::Linear => start.1.lerp(end.1, offset),
+            Interpolation::EaseInOut => start.1.lerp(end.1, ease_in_out(offset)),
+            Interpolation::CatmullRom => {
+                start
+                    .1
+                    .catmull_rom(before.1, end.1, after.1, tangent_scale, offset)
+            }
         }
     }
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
 pub struct AnimationPosition {
     pub left_foot: Point,
     pub left_knee: Point,
@@ -94,7 +527,7 @@ impl AnimationPosition {
         human.joints.clone()
     }
     /// linear interpolation between two positions
-    fn lerp(&self, next: &AnimationPosition, offset: f64) -> Self {
+    pub fn lerp(&self, next: &AnimationPosition, offset: f64) -> Self {
         AnimationPosition {
             left_foot: self.left_foot.lerp(&next.left_foot, offset),
             left_knee: self.left_knee.lerp(&next.left_knee, offset),
@@ -109,6 +542,56 @@ impl AnimationPosition {
             head: self.head.lerp(&next.head, offset),
         }
     }
+    /// Catmull-Rom spline between two positions, shaped by the positions either side
+    fn catmull_rom(
+        &self,
+        previous: &AnimationPosition,
+        next: &AnimationPosition,
+        after: &AnimationPosition,
+        tangent_scale: (f64, f64),
+        offset: f64,
+    ) -> Self {
+        let spline = |p: fn(&AnimationPosition) -> &Point| {
+            p(self).catmull_rom(p(previous), p(next), p(after), tangent_scale, offset)
+        };
+        AnimationPosition {
+            left_foot: spline(|j| &j.left_foot),
+            left_knee: spline(|j| &j.left_knee),
+            hip: spline(|j| &j.hip),
+            right_knee: spline(|j| &j.right_knee),
+            right_foot: spline(|j| &j.right_foot),
+            neck: spline(|j| &j.neck),
+            left_elbow: spline(|j| &j.left_elbow),
+            left_hand: spline(|j| &j.left_hand),
+            right_elbow: spline(|j| &j.right_elbow),
+            right_hand: spline(|j| &j.right_hand),
+            head: spline(|j| &j.head),
+        }
+    }
+    /// Move every joint with the same function
+    pub fn map(&self, f: impl Fn(Point) -> Point) -> Self {
+        AnimationPosition {
+            left_foot: f(self.left_foot),
+            left_knee: f(self.left_knee),
+            hip: f(self.hip),
+            right_knee: f(self.right_knee),
+            right_foot: f(self.right_foot),
+            neck: f(self.neck),
+            left_elbow: f(self.left_elbow),
+            left_hand: f(self.left_hand),
+            right_elbow: f(self.right_elbow),
+            right_hand: f(self.right_hand),
+            head: f(self.head),
+        }
+    }
+    /// Flip the position horizontally about the hip
+    pub fn mirrored(&self) -> Self {
+        let hip = self.hip.x;
+        self.map(|joint| Point {
+            x: 2.0 * hip - joint.x,
+            y: joint.y,
+        })
+    }
     /// Undoes the offset from the initial hip coordinates.
     pub fn apply_offset_by(&self, initial_hip_coords: Point) -> Self {
         AnimationPosition {
@@ -126,3 +609,96 @@ impl AnimationPosition {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::human::Human;
+
+    /// The embedded walk cycle with its first keyframe moved later
+    fn late_walk() -> AnimationsData {
+        let walking = AnimationsData::embedded().expect("the walk cycle should parse");
+        let mut late = (*walking.get("walking").unwrap()).clone();
+        let moved = late.shift_keyframes(&[0], 100.0);
+        assert!(moved > 0.0);
+        let mut animations = AnimationsData::default();
+        animations.insert("walking", late);
+        animations
+    }
+
+    #[test]
+    fn holds_first_keyframe_until_reached() {
+        let animations = late_walk();
+        let late = animations.get("walking").unwrap();
+        let first = late.step(late.keyframes[0].time);
+        assert!(first.is_some());
+        assert_eq!(late.step(0.0), first);
+        assert_eq!(late.step(late.keyframes[0].time / 2.0), first);
+    }
+
+    #[test]
+    fn loops_back_to_a_late_first_keyframe() {
+        let animations = late_walk();
+        let late = animations.get("walking").unwrap();
+        let start = late.keyframes[0].time;
+        let cycle = late.cycle();
+        // every repetition after the first returns to the first keyframe at its time
+        assert_eq!(late.step(cycle + start), late.step(start));
+        assert_ne!(late.step(cycle + start / 2.0), late.step(start));
+        // root motion carries on smoothly through the return
+        let step = 1000.0 / 60.0;
+        let mut time = 0.0;
+        while time < 3.0 * cycle {
+            let jump = late.root_motion(time + step) - late.root_motion(time);
+            assert!(jump.abs() < 0.01, "root motion jumped {} at {}", jump, time);
+            time += step;
+        }
+    }
+
+    #[test]
+    fn human_walks_with_a_late_first_keyframe() {
+        let animations = late_walk();
+        let mut human = Human::new();
+        human.transition(&animations, "walking", 0.0);
+        let mut clock = Clock::new();
+        let start = human.position;
+        for tick in 0..=300 {
+            clock.tick(tick as f64 * 1000.0 / 60.0);
+            while clock.step() {
+                human.update(&animations, &clock);
+            }
+        }
+        assert_ne!(human.position, start);
+    }
+
+    #[test]
+    fn non_finite_times_have_no_position() {
+        let animations = AnimationsData::embedded().unwrap();
+        let walking = animations.get("walking").unwrap();
+        assert_eq!(walking.step(f64::NAN), None);
+        assert_eq!(walking.step(f64::INFINITY), None);
+    }
+
+    #[test]
+    fn shifted_keyframes_stay_in_order() {
+        let animations = AnimationsData::embedded().unwrap();
+        let mut walking = (*animations.get("walking").unwrap()).clone();
+        let times: Vec<f64> = walking.keyframes.iter().map(|k| k.time).collect();
+        // the first keyframe stops short of the second
+        let moved = walking.shift_keyframes(&[0], 1e9);
+        assert_eq!(moved, times[1] - MIN_KEYFRAME_GAP - times[0]);
+        // and can't go before the start
+        assert_eq!(walking.shift_keyframes(&[0], -1e9), -moved);
+        // keyframes moved together only stop at unselected neighbours
+        let moved = walking.shift_keyframes(&[1, 2], -1e9);
+        assert_eq!(moved, times[0] + MIN_KEYFRAME_GAP - times[1]);
+        assert!(walking.keyframes.windows(2).all(|k| k[0].time < k[1].time));
+        // a first keyframe dragged later still plays
+        walking.shift_keyframes(&[0], 1e9);
+        assert!(walking.step(0.0).is_some());
+        assert!(walking.step(walking.cycle() / 3.0).is_some());
+        // nothing selected, nothing moved
+        assert_eq!(walking.shift_keyframes(&[], 100.0), 0.0);
+    }
+}
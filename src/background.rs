@@ -1,34 +1,86 @@
-use crate::animation;
-use yew::prelude::*;
+use crate::human::Point;
+use crate::scene::{Scene, Shape};
 
 #[allow(unused_imports)]
 use crate::trace;
 
+/// Width of `public/background.png` when drawn one unit high
+const DEFAULT_TILE_WIDTH: f64 = 6400.0 / 794.0;
+
+/// Images repeated side by side behind the figures, each scrolling at its own rate
 pub struct Background {
-    offset: f64,
-    total_offset: f64,
-    href: String,
+    /// Drawn in order, so furthest away first
+    pub layers: Vec<Layer>,
+}
+
+/// One row of a repeated image
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Layer {
+    pub href: String,
+    /// Width of one copy of the image, which should match the image's aspect ratio
+    /// at `height` for the copies to meet
+    pub tile_width: f64,
+    #[serde(default = "default_height")]
+    pub height: f64,
+    /// Top of the image
+    #[serde(default)]
+    pub y: f64,
+    /// How far the layer scrolls as the camera moves, from 1 for layers as close as
+    /// the figures down to 0 for layers so far away they stay still on screen
+    #[serde(default = "default_scroll_factor")]
+    pub scroll_factor: f64,
+}
+
+fn default_height() -> f64 {
+    1.0
+}
+
+fn default_scroll_factor() -> f64 {
+    1.0
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 // rendering a Position
 impl Background {
     pub fn new() -> Self {
-        Background {
-            offset: 0.0,
-            total_offset: 0.0,
+        Background::from_layers(vec![Layer {
             href: "./public/background.png".to_string(),
-        }
+            tile_width: DEFAULT_TILE_WIDTH,
+            height: default_height(),
+            y: 0.0,
+            scroll_factor: default_scroll_factor(),
+        }])
     }
-    pub fn view(&self) -> Html {
-        html! {
-            <>
-                <image x={self.offset.to_string()} href={self.href.clone()} height="1"/>
-            </>
-        }
+    pub fn from_layers(layers: Vec<Layer>) -> Self {
+        Background { layers }
     }
-    pub fn apply_offset(&mut self, joints: &animation::AnimationPosition) {
-        /// offset is how much the background will move to the right 
-        /// to match the position of the human
-        let offset = joints.hip;
+    /// Add enough copies of each layer's image to cover the scene's viewport
+    pub fn draw(&self, scene: &mut Scene) {
+        let viewport = scene.viewport;
+        for layer in &self.layers {
+            if layer.tile_width <= 0.0 {
+                continue;
+            }
+            // where the first copy of the image is, having scrolled with the camera
+            let origin = viewport.x0 * (1.0 - layer.scroll_factor);
+            let first = ((viewport.x0 - origin) / layer.tile_width).floor() as i64;
+            let last = ((viewport.x0 + viewport.x1 - origin) / layer.tile_width).ceil() as i64;
+            for tile in first..last {
+                scene.push(Shape::Image {
+                    href: layer.href.clone(),
+                    position: Point {
+                        x: origin + tile as f64 * layer.tile_width,
+                        y: layer.y,
+                    },
+                    width: Some(layer.tile_width),
+                    height: layer.height,
+                });
+            }
+        }
     }
 }
@@ -0,0 +1,170 @@
+//! Render an animation file to a PNG sprite sheet or an animated GIF, without a browser.
+//!
+//! Usage: `render <animation> <output.png|output.gif> [--width px] [--height px] [--fps n] [--columns n]`
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::{env, fs, process};
+use yew_app::camera::Camera;
+use yew_app::{loader, raster};
+
+/// Shortest frame delay browsers play at the given speed, in hundredths of a second
+const MIN_GIF_DELAY: f64 = 2.0;
+
+const USAGE: &str = "usage: render <animation> <output.png|output.gif> [--width px] [--height px] [--fps n] [--columns n]";
+
+struct Options {
+    input: String,
+    output: String,
+    /// Width of each frame in pixels
+    width: usize,
+    /// Height of each frame in pixels, following the viewport if not given
+    height: Option<usize>,
+    frame_rate: f64,
+    /// Frames per row of a sprite sheet
+    columns: usize,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+        let mut positional = vec![];
+        let mut options = Options {
+            input: String::new(),
+            output: String::new(),
+            width: 300,
+            height: None,
+            frame_rate: 12.0,
+            columns: 8,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("missing value for {}", name))
+            };
+            match arg.as_str() {
+                "--width" => options.width = parse_number(&arg, &value(&arg)?)?,
+                "--height" => options.height = Some(parse_number(&arg, &value(&arg)?)?),
+                "--fps" => options.frame_rate = parse_number(&arg, &value(&arg)?)?,
+                "--columns" => options.columns = parse_number(&arg, &value(&arg)?)?,
+                _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+                _ => positional.push(arg),
+            }
+        }
+        // also catches NaN
+        if !(options.frame_rate > 0.0 && options.frame_rate.is_finite()) {
+            return Err(format!("--fps must be above 0, not {}", options.frame_rate));
+        }
+        match <[String; 2]>::try_from(positional) {
+            Ok([input, output]) => {
+                options.input = input;
+                options.output = output;
+                Ok(options)
+            }
+            Err(_) => Err(USAGE.to_string()),
+        }
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", name, value))
+}
+
+fn main() {
+    let result = Options::parse(env::args().skip(1))
+        .map_err(|e| e.into())
+        .and_then(run);
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let extension = options.output.rsplit('.').next().unwrap_or_default();
+    if extension != "png" && extension != "gif" {
+        return Err(format!(
+            "unknown output format for {}, expected .png or .gif",
+            options.output
+        )
+        .into());
+    }
+    let bytes = fs::read(&options.input)?;
+    let animation = loader::parse_animation(&options.input, &bytes)?;
+    // the same area the web app shows
+    let viewport = Camera::new().viewport();
+    let height = options
+        .height
+        .unwrap_or_else(|| (options.width as f64 * viewport.y1 / viewport.x1).round() as usize);
+    let largest = u16::MAX as usize;
+    if extension == "gif" && (options.width > largest || height > largest) {
+        return Err(format!(
+            "{}x{} is too big for a gif, which can be at most {} pixels across",
+            options.width, height, largest
+        )
+        .into());
+    }
+    // browsers play delays under 2 hundredths of a second much slower
+    let delay = gif_delay(options.frame_rate);
+    if extension == "gif" && !(MIN_GIF_DELAY..=u16::MAX as f64).contains(&delay) {
+        return Err(format!(
+            "--fps {} can't be played by a gif, which shows frames for {} to {} hundredths of a second",
+            options.frame_rate,
+            MIN_GIF_DELAY,
+            u16::MAX
+        )
+        .into());
+    }
+    let frames = raster::render_frames(
+        &animation,
+        viewport,
+        options.width,
+        height,
+        options.frame_rate,
+    );
+    if frames.is_empty() {
+        return Err(format!("{} has no keyframes", options.input).into());
+    }
+
+    let writer = BufWriter::new(File::create(&options.output)?);
+    if extension == "png" {
+        write_png(writer, &raster::sprite_sheet(&frames, options.columns))
+    } else {
+        write_gif(writer, &frames, options.frame_rate)
+    }
+}
+
+fn write_png(writer: BufWriter<File>, image: &raster::Raster) -> Result<(), Box<dyn Error>> {
+    let mut encoder = png::Encoder::new(writer, image.width as u32, image.height as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&image.pixels)?;
+    Ok(())
+}
+
+/// Hundredths of a second each frame of a gif is shown for
+fn gif_delay(frame_rate: f64) -> f64 {
+    (100.0 / frame_rate).round()
+}
+
+fn write_gif(
+    writer: BufWriter<File>,
+    frames: &[raster::Raster],
+    frame_rate: f64,
+) -> Result<(), Box<dyn Error>> {
+    let (width, height) = (frames[0].width as u16, frames[0].height as u16);
+    // every shade of grey, so pixels index the palette directly
+    let palette: Vec<u8> = (0..=255).flat_map(|shade| [shade, shade, shade]).collect();
+    let mut encoder = gif::Encoder::new(writer, width, height, &palette)?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    let delay = gif_delay(frame_rate) as u16;
+    for image in frames {
+        let mut frame = gif::Frame::from_indexed_pixels(width, height, &image.pixels[..], None);
+        frame.delay = delay;
+        encoder.write_frame(&frame)?;
+    }
+    Ok(())
+}
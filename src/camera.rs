@@ -0,0 +1,170 @@
+use crate::human::Point;
+
+#[allow(unused_imports)]
+use crate::trace;
+
+/// Width of the view at zoom 1, in world units
+pub const VIEW_WIDTH: f64 = 1.5;
+/// Height of the view at zoom 1, in world units
+pub const VIEW_HEIGHT: f64 = 1.0;
+
+/// The area of the scene shown, as x, y, width and height
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub y1: f64,
+}
+
+/// A rectangle of the world, from its top left to its bottom right corner
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+/// A shake in progress
+struct Shake {
+    start_time: f64,
+    duration: f64,
+    magnitude: f64,
+}
+
+/// Decides which part of the world is shown, following a target around it
+pub struct Camera {
+    /// Centre of the view, in world units
+    pub centre: Point,
+    /// How much bigger things appear than at the default size
+    pub zoom: f64,
+    /// Milliseconds taken to close most (63%) of the distance to the target.
+    /// Zero follows the target exactly.
+    pub smoothing: f64,
+    /// Half the width and height of the box around the centre the target can move
+    /// in without the camera following
+    pub dead_zone: Point,
+    /// Area of the world the view is kept inside, if any
+    pub bounds: Option<Bounds>,
+    shake: Option<Shake>,
+    /// Time of the last update
+    time: Option<f64>,
+    /// Centre before the last update, for drawing between updates
+    previous: Option<Point>,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Camera {
+    /// A camera showing the area from the origin to the default view size
+    pub fn new() -> Self {
+        Camera {
+            centre: Point {
+                x: VIEW_WIDTH / 2.0,
+                y: VIEW_HEIGHT / 2.0,
+            },
+            zoom: 1.0,
+            smoothing: 250.0,
+            dead_zone: Point {
+                x: VIEW_WIDTH / 6.0,
+                y: VIEW_HEIGHT / 2.0,
+            },
+            bounds: None,
+            shake: None,
+            time: None,
+            previous: None,
+        }
+    }
+    /// Move towards a target, for the time since the last update
+    pub fn follow(&mut self, target: Point, time: f64) {
+        let elapsed = self.time.map_or(0.0, |last| (time - last).max(0.0));
+        self.time = Some(time);
+        self.previous = Some(self.centre);
+        // the closest centre that keeps the target inside the dead zone
+        let wanted = Point {
+            x: clamp_within(self.centre.x, target.x, self.dead_zone.x / self.zoom),
+            y: clamp_within(self.centre.y, target.y, self.dead_zone.y / self.zoom),
+        };
+        let progress = if self.smoothing > 0.0 {
+            1.0 - (-elapsed / self.smoothing).exp()
+        } else {
+            1.0
+        };
+        self.centre = self.centre.lerp(&wanted, progress);
+    }
+    /// Shake the view by up to a distance, dying away over some milliseconds
+    pub fn shake(&mut self, magnitude: f64, duration: f64, time: f64) {
+        self.shake = Some(Shake {
+            start_time: time,
+            duration,
+            magnitude,
+        });
+    }
+    /// The area shown, as of the last update
+    pub fn viewport(&self) -> Viewport {
+        self.viewport_at(1.0)
+    }
+    /// The area shown a fraction `alpha` of the way from before the last update to
+    /// after it, to match figures drawn between updates
+    pub fn viewport_at(&self, alpha: f64) -> Viewport {
+        let (width, height) = (VIEW_WIDTH / self.zoom, VIEW_HEIGHT / self.zoom);
+        let mut centre = match self.previous {
+            Some(previous) if alpha < 1.0 => previous.lerp(&self.centre, alpha),
+            _ => self.centre,
+        };
+        if let (Some(shake), Some(time)) = (&self.shake, self.time) {
+            let elapsed = time - shake.start_time;
+            if (0.0..shake.duration).contains(&elapsed) {
+                // two unrelated frequencies, so the shake does not look like a circle
+                let strength = shake.magnitude * (1.0 - elapsed / shake.duration);
+                centre.x += strength * (elapsed * 0.071).sin();
+                centre.y += strength * (elapsed * 0.053).cos();
+            }
+        }
+        if let Some(bounds) = &self.bounds {
+            centre.x = clamp_centre(centre.x, width, bounds.min.x, bounds.max.x);
+            centre.y = clamp_centre(centre.y, height, bounds.min.y, bounds.max.y);
+        }
+        Viewport {
+            x0: centre.x - width / 2.0,
+            y0: centre.y - height / 2.0,
+            x1: width,
+            y1: height,
+        }
+    }
+}
+
+/// The nearest value to `value` that is within `distance` of `target`
+fn clamp_within(value: f64, target: f64, distance: f64) -> f64 {
+    value.clamp(target - distance, target + distance)
+}
+
+/// Keep a view of some size centred at `centre` between `min` and `max`,
+/// or centre it there if it is too big to fit
+fn clamp_centre(centre: f64, size: f64, min: f64, max: f64) -> f64 {
+    if max - min <= size {
+        (min + max) / 2.0
+    } else {
+        centre.clamp(min + size / 2.0, max - size / 2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewport_moves_between_updates_like_the_figures() {
+        let mut camera = Camera::new();
+        camera.smoothing = 0.0;
+        camera.follow(Point { x: 0.75, y: 0.5 }, 0.0);
+        camera.follow(Point { x: 1.75, y: 0.5 }, 16.0);
+        let (before, after) = (camera.viewport_at(0.0), camera.viewport());
+        assert!(after.x0 > before.x0);
+        let halfway = camera.viewport_at(0.5);
+        assert!((halfway.x0 - (before.x0 + after.x0) / 2.0).abs() < 1e-12);
+    }
+}
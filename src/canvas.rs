@@ -0,0 +1,135 @@
+use crate::scene::{Scene, Shape, Stroke};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement};
+
+#[allow(unused_imports)]
+use crate::trace;
+
+/// Draws scenes straight onto a canvas 2D context, skipping the virtual DOM
+#[derive(Default)]
+pub struct CanvasRenderer {
+    /// Images by href, loaded the first time they are drawn
+    images: HashMap<String, HtmlImageElement>,
+}
+
+impl CanvasRenderer {
+    pub fn new() -> Self {
+        CanvasRenderer::default()
+    }
+    /// Clear the canvas and draw a scene on it. Like an svg, the viewport is scaled to
+    /// fit the canvas and centred, keeping its aspect ratio.
+    pub fn draw(&mut self, canvas: &HtmlCanvasElement, scene: &Scene) {
+        // match the backing store to the displayed size, so lines stay sharp
+        let pixel_ratio = web_sys::window().map_or(1.0, |w| w.device_pixel_ratio());
+        let width = (canvas.client_width() as f64 * pixel_ratio).round() as u32;
+        let height = (canvas.client_height() as f64 * pixel_ratio).round() as u32;
+        if canvas.width() != width || canvas.height() != height {
+            canvas.set_width(width);
+            canvas.set_height(height);
+        }
+        let context = canvas
+            .get_context("2d")
+            .expect("unable to get a 2d context")
+            .expect("canvas has no 2d context")
+            .dyn_into::<CanvasRenderingContext2d>()
+            .expect("2d context should be a CanvasRenderingContext2d");
+
+        context
+            .reset_transform()
+            .expect("unable to reset the transform");
+        context.clear_rect(0.0, 0.0, width as f64, height as f64);
+        let viewport = &scene.viewport;
+        let scale = (width as f64 / viewport.x1).min(height as f64 / viewport.y1);
+        context
+            .translate(
+                (width as f64 - viewport.x1 * scale) / 2.0,
+                (height as f64 - viewport.y1 * scale) / 2.0,
+            )
+            .expect("unable to translate the canvas");
+        context
+            .scale(scale, scale)
+            .expect("unable to scale the canvas");
+        context
+            .translate(-viewport.x0, -viewport.y0)
+            .expect("unable to translate the canvas");
+
+        for shape in &scene.shapes {
+            self.draw_shape(&context, shape);
+        }
+    }
+    fn draw_shape(&mut self, context: &CanvasRenderingContext2d, shape: &Shape) {
+        match shape {
+            Shape::Line { a, b, stroke } => {
+                context.begin_path();
+                context.move_to(a.x, a.y);
+                context.line_to(b.x, b.y);
+                set_stroke(context, stroke);
+                context.stroke();
+            }
+            Shape::Circle {
+                centre,
+                radius,
+                stroke,
+                fill,
+            } => {
+                context.begin_path();
+                context
+                    .arc(centre.x, centre.y, *radius, 0.0, 2.0 * PI)
+                    .expect("unable to draw a circle");
+                if let Some(fill) = fill {
+                    #[allow(deprecated)]
+                    context.set_fill_style(&fill.as_str().into());
+                    context.fill();
+                }
+                set_stroke(context, stroke);
+                context.stroke();
+            }
+            Shape::Image {
+                href,
+                position,
+                width,
+                height,
+            } => {
+                let image = self.image(href);
+                // not loaded yet, so try again next frame
+                if !image.complete() || image.natural_height() == 0 {
+                    return;
+                }
+                let aspect_ratio = image.natural_width() as f64 / image.natural_height() as f64;
+                // fit the image into the box and centre it, as svg does
+                let (box_width, box_height) = (width.unwrap_or(height * aspect_ratio), *height);
+                let (draw_width, draw_height) = if box_width / box_height > aspect_ratio {
+                    (box_height * aspect_ratio, box_height)
+                } else {
+                    (box_width, box_width / aspect_ratio)
+                };
+                context
+                    .draw_image_with_html_image_element_and_dw_and_dh(
+                        image,
+                        position.x + (box_width - draw_width) / 2.0,
+                        position.y + (box_height - draw_height) / 2.0,
+                        draw_width,
+                        draw_height,
+                    )
+                    .expect("unable to draw an image");
+            }
+        }
+    }
+    /// The image at an href, starting to load it if it has not been drawn before
+    fn image(&mut self, href: &str) -> &HtmlImageElement {
+        self.images.entry(href.to_string()).or_insert_with(|| {
+            let image = HtmlImageElement::new().expect("unable to create an image");
+            image.set_src(href);
+            image
+        })
+    }
+}
+
+// the string style setters replacing these need a newer web-sys than the one required
+#[allow(deprecated)]
+fn set_stroke(context: &CanvasRenderingContext2d, stroke: &Stroke) {
+    context.set_stroke_style(&stroke.colour.as_str().into());
+    context.set_line_width(stroke.width);
+}
@@ -0,0 +1,148 @@
+#[allow(unused_imports)]
+use crate::trace;
+
+/// Length of one simulation step, in milliseconds of playback
+pub const STEP: f64 = 1000.0 / 60.0;
+/// Most real time taken from one tick, so a throttled or backgrounded tab
+/// carries on where it left off rather than jumping ahead
+pub const MAX_DELTA: f64 = 250.0;
+
+/// Playback time in milliseconds, driven by real time but able to be paused,
+/// sped up, slowed down and moved to any time.
+///
+/// Time moves in fixed steps so playback is the same at any frame rate. Each tick
+/// banks the real time passed, and [`Clock::step`] spends it a step at a time.
+pub struct Clock {
+    time: f64,
+    /// Playback time banked but not yet stepped through
+    accumulator: f64,
+    /// Real time of the last tick
+    last_tick: Option<f64>,
+    /// How many milliseconds of playback pass per real millisecond
+    pub speed: f64,
+    paused: bool,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock {
+    /// A playing clock at time zero
+    pub fn new() -> Self {
+        Clock {
+            time: 0.0,
+            accumulator: 0.0,
+            last_tick: None,
+            speed: 1.0,
+            paused: false,
+        }
+    }
+    /// Bank the real time since the last tick, unless paused
+    pub fn tick(&mut self, real_time: f64) {
+        if let Some(last) = self.last_tick {
+            if !self.paused {
+                self.accumulator += (real_time - last).clamp(0.0, MAX_DELTA) * self.speed;
+            }
+        }
+        self.last_tick = Some(real_time);
+    }
+    /// Move on one step if enough time is banked, returning whether it did.
+    /// Update the simulation after each step.
+    pub fn step(&mut self) -> bool {
+        if self.accumulator < STEP {
+            return false;
+        }
+        self.accumulator -= STEP;
+        self.time += STEP;
+        true
+    }
+    /// Current playback time
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+    /// How far playback is between the last step and the next, from 0 up to 1,
+    /// for drawing between the two. Always 1 while paused
+    pub fn alpha(&self) -> f64 {
+        if self.paused {
+            1.0
+        } else {
+            // rounding can make a bank just short of a step divide to 1
+            (self.accumulator / STEP).min(1.0 - f64::EPSILON)
+        }
+    }
+    /// Move to a playback time
+    pub fn seek(&mut self, time: f64) {
+        self.time = time;
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tick every `interval` ms of real time up to `until`, stepping through it all
+    fn run(clock: &mut Clock, from: f64, until: f64, interval: f64) -> usize {
+        let mut steps = 0;
+        let mut real_time = from;
+        loop {
+            clock.tick(real_time.min(until));
+            while clock.step() {
+                steps += 1;
+                let alpha = clock.alpha();
+                assert!((0.0..1.0).contains(&alpha), "alpha {}", alpha);
+            }
+            if real_time >= until {
+                return steps;
+            }
+            real_time += interval;
+        }
+    }
+
+    #[test]
+    fn same_time_at_any_tick_rate() {
+        let times: Vec<f64> = [7.0, STEP, 33.0]
+            .iter()
+            .map(|&interval| {
+                let mut clock = Clock::new();
+                run(&mut clock, 0.0, 1000.0, interval);
+                clock.time()
+            })
+            .collect();
+        for time in &times {
+            assert!((time - times[0]).abs() < 1e-9, "{:?}", times);
+            // within the step still banked, give or take rounding
+            assert!((1000.0 - time).abs() <= STEP + 1e-9, "{:?}", times);
+        }
+    }
+
+    #[test]
+    fn long_gaps_are_clamped() {
+        let mut clock = Clock::new();
+        clock.tick(0.0);
+        clock.tick(100_000.0);
+        let steps = std::iter::from_fn(|| clock.step().then_some(())).count();
+        assert_eq!(steps, (MAX_DELTA / STEP).round() as usize);
+        assert!(clock.time() <= MAX_DELTA + 1e-9);
+    }
+
+    #[test]
+    fn no_steps_while_paused() {
+        let mut clock = Clock::new();
+        clock.set_paused(true);
+        assert_eq!(run(&mut clock, 0.0, 1000.0, STEP), 0);
+        assert_eq!(clock.time(), 0.0);
+        assert_eq!(clock.alpha(), 1.0);
+        // and none banked for after
+        clock.set_paused(false);
+        assert_eq!(run(&mut clock, 1000.0, 1000.0, STEP), 0);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::animation::{Animation, AnimationPosition, PlaybackMode};
+use crate::human::{self, Human, Point};
+use std::fmt::Write;
+
+#[allow(unused_imports)]
+use crate::trace;
+
+/// Positions sampled per second, on top of every keyframe, so easing, splines and
+/// constant bone lengths survive SVG's straight line interpolation
+const SAMPLE_RATE: f64 = 30.0;
+/// Space left around the figure, in viewport units
+const MARGIN: f64 = 0.05;
+
+/// Reads one coordinate of one end of a line
+type LineEnd = fn(&human::Position) -> f64;
+
+/// Render an animation as a standalone svg file, moving the ends of each line of the
+/// figure with SMIL `<animate>` elements
+pub fn animated_svg(animation: &Animation) -> String {
+    let cycle = animation.cycle();
+    // how long the animation repeats for and whether the end is held
+    let (repeat, fill) = match animation.playback {
+        PlaybackMode::Once => (r#"repeatCount="1""#.to_string(), "remove"),
+        PlaybackMode::Clamp => (r#"repeatCount="1""#.to_string(), "freeze"),
+        PlaybackMode::Loop | PlaybackMode::PingPong => {
+            (r#"repeatCount="indefinite""#.to_string(), "remove")
+        }
+        // stop at the last keyframe of the last repetition, as playback does,
+        // rather than after looping back to the first
+        PlaybackMode::LoopTimes(times) => {
+            let end = times as f64 * cycle - animation.loop_interval;
+            (format!(r#"repeatDur="{}ms""#, end.max(0.0)), "freeze")
+        }
+    };
+
+    let mut times = vec![0.0];
+    if cycle > 0.0 {
+        let samples = (cycle / 1000.0 * SAMPLE_RATE).ceil() as usize;
+        times.extend((1..=samples).map(|i| i as f64 * cycle / samples as f64));
+        for keyframe in &animation.keyframes {
+            times.push(keyframe.time);
+            if animation.playback == PlaybackMode::PingPong {
+                times.push(cycle - keyframe.time);
+            }
+        }
+        times.retain(|time| (0.0..=cycle).contains(time));
+        times.sort_by(f64::total_cmp);
+        times.dedup_by(|a, b| (*a - *b).abs() < 1e-6);
+    }
+    let positions: Vec<AnimationPosition> = times
+        .iter()
+        .filter_map(|time| animation.position_at(*time))
+        .collect();
+    let lines: Vec<[human::Position; 10]> = positions.iter().map(Human::lines).collect();
+    let first = match lines.first() {
+        Some(first) => first,
+        None => return empty_svg(),
+    };
+
+    let mut svg = String::new();
+    let (min, max) = bounds(&lines);
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        min.x - MARGIN,
+        min.y - MARGIN,
+        max.x - min.x + 2.0 * MARGIN,
+        max.y - min.y + 2.0 * MARGIN
+    )
+    .unwrap();
+    let key_times = join(times.iter().map(|time| time / cycle));
+    for (i, line) in first.iter().enumerate() {
+        writeln!(
+            svg,
+            r#"  <line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}">"#,
+            line.a.x,
+            line.a.y,
+            line.b.x,
+            line.b.y,
+            human::LINE_COLOUR,
+            human::LINE_WIDTH
+        )
+        .unwrap();
+        if lines.len() > 1 {
+            let ends: [(&str, LineEnd); 4] = [
+                ("x1", |line| line.a.x),
+                ("y1", |line| line.a.y),
+                ("x2", |line| line.b.x),
+                ("y2", |line| line.b.y),
+            ];
+            for (attribute, end) in ends {
+                writeln!(
+                    svg,
+                    r#"    <animate attributeName="{}" dur="{}ms" {} fill="{}" keyTimes="{}" values="{}" />"#,
+                    attribute,
+                    cycle,
+                    repeat,
+                    fill,
+                    key_times,
+                    join(lines.iter().map(|frame| end(&frame[i])))
+                )
+                .unwrap();
+            }
+        }
+        svg.push_str("  </line>\n");
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn empty_svg() -> String {
+    r#"<svg xmlns="http://www.w3.org/2000/svg" />"#.to_string()
+}
+
+/// Smallest and largest coordinates of every line end
+fn bounds(lines: &[[human::Position; 10]]) -> (Point, Point) {
+    let ends = lines.iter().flatten().flat_map(|line| [line.a, line.b]);
+    let mut min = Point {
+        x: f64::INFINITY,
+        y: f64::INFINITY,
+    };
+    let mut max = Point {
+        x: f64::NEG_INFINITY,
+        y: f64::NEG_INFINITY,
+    };
+    for end in ends {
+        min.x = min.x.min(end.x);
+        min.y = min.y.min(end.y);
+        max.x = max.x.max(end.x);
+        max.y = max.y.max(end.y);
+    }
+    (min, max)
+}
+
+/// Values separated by semicolons, as SMIL lists are
+fn join(values: impl Iterator<Item = f64>) -> String {
+    values
+        .map(|value| format!("{:.5}", value))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_loops_stop_on_the_last_keyframe() {
+        let mut animation = Animation::new();
+        animation.push(Human::new().joints);
+        animation.push(Human::new().joints.map(|joint| Point {
+            x: joint.x + 0.1,
+            y: joint.y,
+        }));
+        animation.loop_interval = 200.0;
+        animation.playback = PlaybackMode::LoopTimes(2);
+        // two cycles of 400ms, less the final loop back to the first keyframe
+        let svg = animated_svg(&animation);
+        assert!(
+            svg.contains(r#"dur="400ms" repeatDur="600ms" fill="freeze""#),
+            "{}",
+            svg
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::{HashMap, VecDeque};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlVideoElement, Url};
+use yew::Callback;
+
+#[allow(unused_imports)]
+use crate::trace;
+
+/// Frames per second assumed for picked footage until told otherwise
+const DEFAULT_FRAME_RATE: f64 = 24.0;
+
+/// The film poses are traced over, one frame at a time
+pub struct Footage {
+    source: Source,
+    frame_rate: f64,
+}
+
+enum Source {
+    /// Frames extracted beforehand to `public/output_NNN.png`, numbered from 1
+    Extracted,
+    /// Image files picked by the user, as object URLs in name order
+    Images(Vec<String>),
+    Video(Video),
+}
+
+/// A video file picked by the user, with frames captured as the video is sought to them
+struct Video {
+    element: HtmlVideoElement,
+    url: String,
+    /// Data URLs of the frames captured so far, by frame index
+    frames: HashMap<usize, String>,
+    /// Frames waiting to be captured, in the order they were asked for
+    queue: VecDeque<usize>,
+    /// The frame being sought to
+    seeking: Option<usize>,
+    /// Whether enough of the video has loaded to seek in it
+    loaded: bool,
+    /// Event listeners on the video, kept alive for as long as it is
+    listeners: Vec<Listener>,
+}
+
+/// An event name and what to do when it fires
+type Listener = (&'static str, Closure<dyn FnMut()>);
+
+impl Default for Footage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Footage {
+    /// The frames extracted to `public/`
+    pub fn new() -> Self {
+        Footage {
+            source: Source::Extracted,
+            frame_rate: DEFAULT_FRAME_RATE,
+        }
+    }
+    /// Replace the footage with picked files: a video if the first file is one,
+    /// otherwise images in name order. `on_loaded` and `on_seeked` are called as
+    /// a video becomes ready to seek and finishes seeking.
+    pub fn load(
+        &mut self,
+        mut files: Vec<web_sys::File>,
+        on_loaded: Callback<()>,
+        on_seeked: Callback<()>,
+    ) {
+        let object_url = |file: &web_sys::File| {
+            Url::create_object_url_with_blob(file).expect("unable to create an object url")
+        };
+        self.source = match files.first() {
+            Some(file) if file.type_().starts_with("video/") => {
+                let url = object_url(file);
+                let element = web_sys::window()
+                    .and_then(|window| window.document())
+                    .expect("should have a document on window")
+                    .create_element("video")
+                    .expect("unable to create a video element")
+                    .dyn_into::<HtmlVideoElement>()
+                    .expect("should be a video element");
+                element.set_muted(true);
+                element.set_preload("auto");
+                let listeners: Vec<Listener> = vec![
+                    (
+                        "loadeddata",
+                        Closure::wrap(Box::new(move || on_loaded.emit(()))),
+                    ),
+                    (
+                        "seeked",
+                        Closure::wrap(Box::new(move || on_seeked.emit(()))),
+                    ),
+                ];
+                for (event, listener) in &listeners {
+                    element
+                        .add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())
+                        .expect("unable to listen to the video");
+                }
+                element.set_src(&url);
+                Source::Video(Video {
+                    element,
+                    url,
+                    frames: HashMap::new(),
+                    queue: VecDeque::new(),
+                    seeking: None,
+                    loaded: false,
+                    listeners,
+                })
+            }
+            Some(_) => {
+                files.sort_by_key(|file| file.name());
+                Source::Images(files.iter().map(object_url).collect())
+            }
+            None => return,
+        };
+    }
+    /// Frames per second of picked footage, or none for the extracted frames,
+    /// whose timing is unknown
+    pub fn frame_rate(&self) -> Option<f64> {
+        match self.source {
+            Source::Extracted => None,
+            _ => Some(self.frame_rate),
+        }
+    }
+    /// Change how many frames a second of picked footage is split into
+    pub fn set_frame_rate(&mut self, frame_rate: f64) {
+        if frame_rate <= 0.0 {
+            return;
+        }
+        self.frame_rate = frame_rate;
+        // every frame is now at a different time
+        if let Source::Video(video) = &mut self.source {
+            video.frames.clear();
+            video.queue.clear();
+            video.seeking = None;
+        }
+    }
+    /// Milliseconds into the footage a frame (counting from 0) is shown
+    pub fn time(&self, index: usize) -> Option<f64> {
+        Some(index as f64 * 1000.0 / self.frame_rate()?)
+    }
+    /// The frame (counting from 0) shown at a time in milliseconds
+    pub fn frame_at(&self, time: f64) -> Option<usize> {
+        Some((time * self.frame_rate()? / 1000.0).round().max(0.0) as usize)
+    }
+    /// Image of a frame (counting from 0) to draw, if it is available yet
+    pub fn frame(&self, index: usize) -> Option<String> {
+        match &self.source {
+            Source::Extracted => Some(format!("./public/output_{:0>3}.png", index + 1)),
+            Source::Images(urls) => urls.get(index).cloned(),
+            Source::Video(video) => video.frames.get(&index).cloned(),
+        }
+    }
+    /// Ask for a frame of a video to be captured, if it has not been already
+    pub fn request(&mut self, index: usize) {
+        if let Source::Video(video) = &mut self.source {
+            if !video.frames.contains_key(&index)
+                && video.seeking != Some(index)
+                && !video.queue.contains(&index)
+            {
+                video.queue.push_back(index);
+            }
+        }
+        self.seek_next();
+    }
+    /// The video can now be sought
+    pub fn loaded(&mut self) {
+        if let Source::Video(video) = &mut self.source {
+            video.loaded = true;
+        }
+        self.seek_next();
+    }
+    /// The video has reached the frame it was seeking, so capture it.
+    /// Returns whether a frame was captured.
+    pub fn seeked(&mut self) -> bool {
+        let captured = match &mut self.source {
+            Source::Video(video) => match video.seeking.take() {
+                Some(index) => match capture(&video.element) {
+                    Some(frame) => video.frames.insert(index, frame).is_none(),
+                    None => false,
+                },
+                None => false,
+            },
+            _ => false,
+        };
+        self.seek_next();
+        captured
+    }
+    /// Seek to the next frame waiting to be captured, unless already seeking
+    fn seek_next(&mut self) {
+        let frame_rate = self.frame_rate;
+        if let Source::Video(video) = &mut self.source {
+            if !video.loaded || video.seeking.is_some() {
+                return;
+            }
+            while let Some(index) = video.queue.pop_front() {
+                if !video.frames.contains_key(&index) {
+                    // the middle of the frame, so rounding does not land on its neighbour
+                    video
+                        .element
+                        .set_current_time((index as f64 + 0.5) / frame_rate);
+                    video.seeking = Some(index);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Source {
+    fn drop(&mut self) {
+        match self {
+            Source::Extracted => {}
+            Source::Images(urls) => {
+                for url in urls {
+                    Url::revoke_object_url(url).expect("unable to revoke an object url");
+                }
+            }
+            Source::Video(video) => {
+                for (event, listener) in &video.listeners {
+                    video
+                        .element
+                        .remove_event_listener_with_callback(
+                            event,
+                            listener.as_ref().unchecked_ref(),
+                        )
+                        .expect("unable to stop listening to the video");
+                }
+                Url::revoke_object_url(&video.url).expect("unable to revoke an object url");
+            }
+        }
+    }
+}
+
+/// The frame a video is showing, as a data URL
+fn capture(element: &HtmlVideoElement) -> Option<String> {
+    let canvas = web_sys::window()?
+        .document()?
+        .create_element("canvas")
+        .ok()?
+        .dyn_into::<HtmlCanvasElement>()
+        .ok()?;
+    canvas.set_width(element.video_width());
+    canvas.set_height(element.video_height());
+    let context = canvas
+        .get_context("2d")
+        .ok()??
+        .dyn_into::<CanvasRenderingContext2d>()
+        .ok()?;
+    context
+        .draw_image_with_html_video_element(element, 0.0, 0.0)
+        .ok()?;
+    canvas.to_data_url_with_type("image/jpeg").ok()
+}
@@ -0,0 +1,325 @@
+use crate::animation::{
+    Animation, AnimationPosition, Interpolation, Keyframe, PlaybackMode,
+    ANIMATION_POSITION_INTERVAL,
+};
+use crate::skeleton::Skeleton;
+use std::convert::TryFrom;
+
+#[allow(unused_imports)]
+use crate::trace;
+
+/// Identifies a file as a stick figure animation
+pub const FORMAT_NAME: &str = "stick-animation";
+/// Version of the file format written. Increase when the layout changes and
+/// add a migration from the previous version to `migrate`.
+pub const FORMAT_VERSION: u32 = 1;
+/// Unit of every time in the file
+const TIME_UNIT: &str = "milliseconds";
+/// Joints of the stick figure, in the order they are saved
+const JOINTS: [&str; 11] = [
+    "left_foot",
+    "left_knee",
+    "hip",
+    "right_knee",
+    "right_foot",
+    "neck",
+    "left_elbow",
+    "left_hand",
+    "right_elbow",
+    "right_hand",
+    "head",
+];
+/// Pairs of joints joined by a bone
+const BONES: [(&str, &str); 10] = [
+    ("left_foot", "left_knee"),
+    ("left_knee", "hip"),
+    ("hip", "right_knee"),
+    ("right_knee", "right_foot"),
+    ("hip", "neck"),
+    ("neck", "left_elbow"),
+    ("left_elbow", "left_hand"),
+    ("neck", "right_elbow"),
+    ("right_elbow", "right_hand"),
+    ("neck", "head"),
+];
+
+/// Descriptive information saved with an animation
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Metadata {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Versioned container animations are saved in
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct AnimationFile {
+    pub header: Header,
+    pub playback: PlaybackMode,
+    pub interpolation: Interpolation,
+    pub loop_interval: f64,
+    pub keyframes: Vec<Keyframe>,
+}
+
+/// Describes the contents of an animation file
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Header {
+    /// Always [`FORMAT_NAME`]
+    pub format: String,
+    pub version: u32,
+    pub metadata: Metadata,
+    pub timing: Timing,
+    pub skeleton: SkeletonDefinition,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Timing {
+    /// Always [`TIME_UNIT`]
+    pub time_unit: String,
+    /// Time of the last keyframe
+    pub duration: f64,
+    /// Frames per second of the footage the animation was traced from
+    pub frame_rate: Option<f64>,
+}
+
+/// The joints and bones keyframe positions are made of
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct SkeletonDefinition {
+    pub joints: Vec<String>,
+    pub bones: Vec<(String, String)>,
+    /// Bone lengths held constant during playback, if any
+    pub lengths: Option<Skeleton>,
+}
+
+impl From<Animation> for AnimationFile {
+    fn from(animation: Animation) -> Self {
+        AnimationFile {
+            header: Header {
+                format: FORMAT_NAME.to_string(),
+                version: FORMAT_VERSION,
+                timing: Timing {
+                    time_unit: TIME_UNIT.to_string(),
+                    duration: animation.duration(),
+                    frame_rate: animation.frame_rate,
+                },
+                metadata: animation.metadata,
+                skeleton: SkeletonDefinition {
+                    joints: JOINTS.iter().map(|j| j.to_string()).collect(),
+                    bones: BONES
+                        .iter()
+                        .map(|(a, b)| (a.to_string(), b.to_string()))
+                        .collect(),
+                    lengths: animation.skeleton,
+                },
+            },
+            playback: animation.playback,
+            interpolation: animation.interpolation,
+            loop_interval: animation.loop_interval,
+            keyframes: animation.keyframes,
+        }
+    }
+}
+
+/// Layouts an animation may be stored in, newest first
+pub(crate) enum SerializedAnimation {
+    Versioned(Box<AnimationFile>),
+    Timed(TimedAnimation),
+    Untimed(UntimedAnimation),
+}
+
+impl<'de> serde::Deserialize<'de> for SerializedAnimation {
+    /// Reads the layout named by the file's `header`, `keyframes` or `positions` key,
+    /// so a mistake in the file is reported rather than it matching no layout
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let layout = if value.get("header").is_some() {
+            serde_json::from_value(value).map(|file| SerializedAnimation::Versioned(Box::new(file)))
+        } else if value.get("keyframes").is_some() {
+            serde_json::from_value(value).map(SerializedAnimation::Timed)
+        } else if value.get("positions").is_some() {
+            serde_json::from_value(value).map(SerializedAnimation::Untimed)
+        } else {
+            return Err(D::Error::custom(
+                "expected a `header`, `keyframes` or `positions` field",
+            ));
+        };
+        layout.map_err(D::Error::custom)
+    }
+}
+
+/// Keyframes with times, from before the format was versioned
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TimedAnimation {
+    keyframes: Vec<Keyframe>,
+    #[serde(default)]
+    playback: PlaybackMode,
+    #[serde(default)]
+    interpolation: Interpolation,
+    #[serde(default = "default_loop_interval")]
+    loop_interval: f64,
+    #[serde(default)]
+    skeleton: Option<Skeleton>,
+}
+
+/// Positions without times, assumed to be evenly spaced
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct UntimedAnimation {
+    positions: Vec<AnimationPosition>,
+}
+
+fn default_loop_interval() -> f64 {
+    ANIMATION_POSITION_INTERVAL
+}
+
+impl TryFrom<SerializedAnimation> for Animation {
+    type Error = String;
+
+    fn try_from(serialized: SerializedAnimation) -> Result<Self, Self::Error> {
+        let mut animation = match serialized {
+            SerializedAnimation::Versioned(file) => migrate(*file)?,
+            SerializedAnimation::Timed(TimedAnimation {
+                keyframes,
+                playback,
+                interpolation,
+                loop_interval,
+                skeleton,
+            }) => Animation {
+                keyframes,
+                playback,
+                interpolation,
+                loop_interval,
+                skeleton,
+                ..Animation::new()
+            },
+            SerializedAnimation::Untimed(UntimedAnimation { positions }) => {
+                let mut animation = Animation::new();
+                for position in positions {
+                    animation.push(position);
+                }
+                animation
+            }
+        };
+        animation
+            .keyframes
+            .sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(animation)
+    }
+}
+
+/// Bring a versioned file up to the current version and read the animation from it
+fn migrate(file: AnimationFile) -> Result<Animation, String> {
+    let header = &file.header;
+    if header.format != FORMAT_NAME {
+        return Err(format!("not a {} file", FORMAT_NAME));
+    }
+    if header.version > FORMAT_VERSION {
+        return Err(format!(
+            "file version {} is newer than the supported version {}",
+            header.version, FORMAT_VERSION
+        ));
+    }
+    if header.timing.time_unit != TIME_UNIT {
+        return Err(format!("unsupported time unit {}", header.timing.time_unit));
+    }
+    if header.skeleton.joints != JOINTS {
+        return Err(format!(
+            "unsupported skeleton with joints {:?}",
+            header.skeleton.joints
+        ));
+    }
+    // migrations between versions go here, oldest first
+    Ok(Animation {
+        keyframes: file.keyframes,
+        playback: file.playback,
+        interpolation: file.interpolation,
+        loop_interval: file.loop_interval,
+        skeleton: file.header.skeleton.lengths,
+        metadata: file.header.metadata,
+        frame_rate: file.header.timing.frame_rate,
+    })
+}
+
+/// Encodings an animation file can be written in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Cbor,
+    /// Pretty-printed, for reading and editing by hand
+    Json,
+    /// Pretty-printed, for reading and editing by hand
+    Ron,
+}
+
+impl Encoding {
+    /// File extension used for the encoding
+    pub fn extension(self) -> &'static str {
+        match self {
+            Encoding::Cbor => "cbor",
+            Encoding::Json => "json",
+            Encoding::Ron => "ron",
+        }
+    }
+    /// Work out the encoding of a file from its extension, or failing that its first bytes
+    pub fn detect(file: &str, bytes: &[u8]) -> Option<Encoding> {
+        let extension = file.rsplit('.').next().unwrap_or_default();
+        match extension.to_ascii_lowercase().as_str() {
+            "cbor" => return Some(Encoding::Cbor),
+            "json" => return Some(Encoding::Json),
+            "ron" => return Some(Encoding::Ron),
+            _ => {}
+        }
+        match bytes.iter().find(|b| !b.is_ascii_whitespace())? {
+            b'{' => Some(Encoding::Json),
+            // unnamed struct, named struct or extension attribute
+            b'(' | b'#' => Some(Encoding::Ron),
+            b if b.is_ascii_alphabetic() => Some(Encoding::Ron),
+            // map or self-describing tag
+            0xa0..=0xbf | 0xd9 => Some(Encoding::Cbor),
+            _ => None,
+        }
+    }
+    pub fn encode(self, animation: &Animation) -> Result<Vec<u8>, String> {
+        match self {
+            Encoding::Cbor => serde_cbor::to_vec(animation).map_err(|e| e.to_string()),
+            Encoding::Json => serde_json::to_vec_pretty(animation).map_err(|e| e.to_string()),
+            Encoding::Ron => {
+                ron::ser::to_string_pretty(animation, ron::ser::PrettyConfig::default())
+                    .map(String::into_bytes)
+                    .map_err(|e| e.to_string())
+            }
+        }
+    }
+    pub fn decode(self, bytes: &[u8]) -> Result<Animation, String> {
+        match self {
+            Encoding::Cbor => serde_cbor::from_slice(bytes).map_err(|e| e.to_string()),
+            Encoding::Json => serde_json::from_slice(bytes).map_err(|e| e.to_string()),
+            // RON cannot be read without knowing the layout, and was only ever versioned anyway
+            Encoding::Ron => {
+                let file: AnimationFile = ron::de::from_bytes(bytes).map_err(|e| e.to_string())?;
+                Animation::try_from(SerializedAnimation::Versioned(Box::new(file)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_the_mistake_in_a_layout() {
+        let decode = |json: &str| Encoding::Json.decode(json.as_bytes()).unwrap_err();
+        let error = decode(r#"{"keyframes": [{"position": {}}]}"#);
+        assert!(error.contains("missing field `left_foot`"), "{}", error);
+        let error = decode(r#"{"positions": 1}"#);
+        assert!(error.contains("invalid type"), "{}", error);
+        let error = decode(r#"{"frames": []}"#);
+        assert!(
+            error.contains("`header`, `keyframes` or `positions`"),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,332 @@
+use crate::animation::{Animation, AnimationPosition, Keyframe};
+
+#[allow(unused_imports)]
+use crate::trace;
+
+/// A reversible change to the frames saved so far, or to the pose being traced
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// Move a joint of the pose being traced, which the history's cursors restore
+    MovePose {
+        joint: &'static str,
+    },
+    InsertKeyframe {
+        index: usize,
+        keyframe: Keyframe,
+    },
+    RemoveKeyframe {
+        index: usize,
+        keyframe: Keyframe,
+    },
+    /// Replace the keyframe at an index, as overwriting a saved frame does
+    SetKeyframe {
+        index: usize,
+        before: Keyframe,
+        after: Keyframe,
+    },
+    /// Take a keyframe out and put a changed one back in time order, as overwriting a
+    /// frame with a pose from another time in the footage does
+    MoveKeyframe {
+        from: usize,
+        to: usize,
+        before: Keyframe,
+        after: Keyframe,
+    },
+    /// Insert a keyframe at the time of the one at its index, moving every later
+    /// keyframe along to the next time (see [`Animation::insert_position`])
+    InsertPosition {
+        index: usize,
+        keyframe: Keyframe,
+    },
+    /// Remove a keyframe, moving every later keyframe back to the previous time
+    /// (see [`Animation::remove_position`])
+    RemovePosition {
+        index: usize,
+        keyframe: Keyframe,
+        /// Time of the last keyframe, which removing drops
+        last_time: f64,
+    },
+    /// Exchange the positions of two keyframes, leaving their times in place
+    SwapPositions {
+        a: usize,
+        b: usize,
+    },
+    /// Move the times of some keyframes by the same amount, which they are known to fit
+    /// (see [`Animation::shift_keyframes`])
+    ShiftKeyframes {
+        indices: Vec<usize>,
+        delta: f64,
+    },
+    /// Swap out every frame at once, as imports do
+    ReplaceAnimation {
+        description: &'static str,
+        before: Box<Animation>,
+        after: Box<Animation>,
+    },
+}
+
+impl Command {
+    /// Make the change
+    pub fn apply(&self, animation: &mut Animation) {
+        match self {
+            Command::MovePose { .. } => {}
+            Command::InsertKeyframe { index, keyframe } => {
+                animation.keyframes.insert(*index, keyframe.clone())
+            }
+            Command::RemoveKeyframe { index, .. } => {
+                animation.keyframes.remove(*index);
+            }
+            Command::SetKeyframe { index, after, .. } => {
+                animation.keyframes[*index] = after.clone()
+            }
+            Command::MoveKeyframe {
+                from, to, after, ..
+            } => {
+                animation.keyframes.remove(*from);
+                animation.keyframes.insert(*to, after.clone());
+            }
+            Command::InsertPosition { index, keyframe } => {
+                animation.insert_position(*index, keyframe.clone())
+            }
+            Command::RemovePosition { index, .. } => {
+                animation.remove_position(*index);
+            }
+            Command::SwapPositions { a, b } => animation.swap_positions(*a, *b),
+            Command::ShiftKeyframes { indices, delta } => {
+                animation.shift_keyframes(indices, *delta);
+            }
+            Command::ReplaceAnimation { after, .. } => *animation = (**after).clone(),
+        }
+    }
+    /// Take the change back, from the state just after it was made
+    pub fn revert(&self, animation: &mut Animation) {
+        match self {
+            Command::MovePose { .. } => {}
+            Command::InsertKeyframe { index, .. } => {
+                animation.keyframes.remove(*index);
+            }
+            Command::RemoveKeyframe { index, keyframe } => {
+                animation.keyframes.insert(*index, keyframe.clone())
+            }
+            Command::SetKeyframe { index, before, .. } => {
+                animation.keyframes[*index] = before.clone()
+            }
+            Command::MoveKeyframe {
+                from, to, before, ..
+            } => {
+                animation.keyframes.remove(*to);
+                animation.keyframes.insert(*from, before.clone());
+            }
+            Command::InsertPosition { index, .. } => {
+                animation.remove_position(*index);
+            }
+            Command::RemovePosition {
+                index,
+                keyframe,
+                last_time,
+            } => {
+                animation.insert_position(*index, keyframe.clone());
+                if let Some(last) = animation.keyframes.last_mut() {
+                    last.time = *last_time;
+                }
+            }
+            Command::SwapPositions { a, b } => animation.swap_positions(*a, *b),
+            Command::ShiftKeyframes { indices, delta } => {
+                animation.shift_keyframes(indices, -*delta);
+            }
+            Command::ReplaceAnimation { before, .. } => *animation = (**before).clone(),
+        }
+    }
+    /// What the command does, for the history list
+    pub fn describe(&self) -> String {
+        match self {
+            Command::MovePose { joint } => format!("Move {}", joint),
+            Command::InsertKeyframe { index, .. } => format!("Add frame {}", index),
+            Command::RemoveKeyframe { index, .. } => format!("Remove frame {}", index),
+            Command::SetKeyframe { index, .. } => format!("Overwrite frame {}", index),
+            Command::MoveKeyframe { from, .. } => format!("Overwrite frame {}", from),
+            Command::InsertPosition { index, .. } => format!("Insert frame {}", index),
+            Command::RemovePosition { index, .. } => format!("Delete frame {}", index),
+            Command::SwapPositions { a, b } => format!("Swap frames {} and {}", a, b),
+            Command::ShiftKeyframes { indices, .. } if indices.len() == 1 => {
+                format!("Retime frame {}", indices[0])
+            }
+            Command::ShiftKeyframes { indices, .. } => format!("Retime {} frames", indices.len()),
+            Command::ReplaceAnimation { description, .. } => description.to_string(),
+        }
+    }
+}
+
+/// Which saved frame is being edited (or one past the last for a new frame),
+/// and the pose being traced for it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    pub index: usize,
+    pub pose: AnimationPosition,
+}
+
+/// A command made, with the cursor just before and after it
+#[derive(Debug, Clone)]
+struct Entry {
+    command: Command,
+    before: Cursor,
+    after: Cursor,
+}
+
+/// Commands made so far, which can be undone and redone in turn
+#[derive(Default)]
+pub struct History {
+    done: Vec<Entry>,
+    /// Most recently undone last
+    undone: Vec<Entry>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History::default()
+    }
+    /// Remember a change that has already been made, forgetting anything undone
+    pub fn record(&mut self, command: Command, before: Cursor, after: Cursor) {
+        self.done.push(Entry {
+            command,
+            before,
+            after,
+        });
+        self.undone.clear();
+    }
+    /// Take back the last change. Returns the cursor from before it, to go back to,
+    /// or none if there is nothing to undo
+    pub fn undo(&mut self, animation: &mut Animation) -> Option<Cursor> {
+        let entry = self.done.pop()?;
+        entry.command.revert(animation);
+        let cursor = entry.before.clone();
+        self.undone.push(entry);
+        Some(cursor)
+    }
+    /// Make the last undone change again. Returns the cursor from after it, to go
+    /// back to, or none if there is nothing to redo
+    pub fn redo(&mut self, animation: &mut Animation) -> Option<Cursor> {
+        let entry = self.undone.pop()?;
+        entry.command.apply(animation);
+        let cursor = entry.after.clone();
+        self.done.push(entry);
+        Some(cursor)
+    }
+    /// Every command made, oldest first, with whether it is currently undone
+    pub fn entries(&self) -> impl Iterator<Item = (&Command, bool)> {
+        let done = self.done.iter().map(|entry| (&entry.command, false));
+        let undone = self.undone.iter().rev().map(|entry| (&entry.command, true));
+        done.chain(undone)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::human::{Human, Point};
+
+    fn pose(x: f64) -> AnimationPosition {
+        Human::new().joints.map(|_| Point { x, y: 0.0 })
+    }
+
+    fn frames(animation: &Animation) -> Vec<(f64, AnimationPosition)> {
+        let keyframes = animation.keyframes.iter();
+        keyframes.map(|k| (k.time, k.position.clone())).collect()
+    }
+
+    #[test]
+    fn undo_returns_to_the_frame_a_pose_was_traced_for() {
+        let mut animation = Animation::new();
+        animation.push(pose(0.0));
+        animation.push(pose(1.0));
+        let mut history = History::new();
+        let cursor = |index, x| Cursor {
+            index,
+            pose: pose(x),
+        };
+        // drag on the new frame, save it, then load the next (empty) frame
+        history.record(
+            Command::MovePose { joint: "hip" },
+            cursor(2, 1.0),
+            cursor(2, 5.0),
+        );
+        let keyframe = Keyframe {
+            time: animation.next_keyframe_time(),
+            position: pose(5.0),
+            interpolation: None,
+            reference_time: None,
+        };
+        let command = Command::InsertKeyframe { index: 2, keyframe };
+        command.apply(&mut animation);
+        history.record(command, cursor(2, 5.0), cursor(3, 5.0));
+
+        assert_eq!(history.undo(&mut animation), Some(cursor(2, 5.0)));
+        assert_eq!(animation.keyframes.len(), 2);
+        assert_eq!(history.undo(&mut animation), Some(cursor(2, 1.0)));
+        assert_eq!(history.undo(&mut animation), None);
+        assert_eq!(history.redo(&mut animation), Some(cursor(2, 5.0)));
+        assert_eq!(history.redo(&mut animation), Some(cursor(3, 5.0)));
+        assert_eq!(animation.keyframes[2].position, pose(5.0));
+    }
+
+    #[test]
+    fn frame_edits_revert_to_the_same_frames() {
+        let mut animation = Animation::new();
+        for x in 0..4 {
+            animation.push(pose(x as f64));
+        }
+        animation.keyframes[3].time = 500.0;
+        let original = frames(&animation);
+        let keyframe = |x| Keyframe {
+            time: 0.0,
+            position: pose(x),
+            interpolation: None,
+            reference_time: None,
+        };
+        let commands = vec![
+            Command::SetKeyframe {
+                index: 1,
+                before: animation.keyframes[1].clone(),
+                after: keyframe(9.0),
+            },
+            Command::InsertPosition {
+                index: 1,
+                keyframe: keyframe(9.0),
+            },
+            Command::InsertPosition {
+                index: 4,
+                keyframe: keyframe(9.0),
+            },
+            Command::RemovePosition {
+                index: 1,
+                keyframe: animation.keyframes[1].clone(),
+                last_time: 500.0,
+            },
+            Command::RemovePosition {
+                index: 3,
+                keyframe: animation.keyframes[3].clone(),
+                last_time: 500.0,
+            },
+            Command::MoveKeyframe {
+                from: 0,
+                to: 2,
+                before: animation.keyframes[0].clone(),
+                after: Keyframe {
+                    time: 450.0,
+                    ..keyframe(9.0)
+                },
+            },
+            Command::SwapPositions { a: 2, b: 3 },
+            Command::ShiftKeyframes {
+                indices: vec![1, 2],
+                delta: 50.0,
+            },
+        ];
+        for command in commands {
+            command.apply(&mut animation);
+            assert_ne!(frames(&animation), original, "{}", command.describe());
+            command.revert(&mut animation);
+            assert_eq!(frames(&animation), original, "{}", command.describe());
+        }
+    }
+}
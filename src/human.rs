@@ -1,16 +1,12 @@
 use crate::animation;
-use yew::prelude::*;
+use crate::clock::Clock;
+use crate::scene::{Scene, Shape, Stroke};
+use std::rc::Rc;
 
 #[allow(unused_imports)]
 use crate::trace;
 
-lazy_static! {
-    /// Static variable that contains the loaded animations
-    static ref ANIMATIONS_DATA: animation::AnimationsData =
-        animation::AnimationsData::load_animations();
-}
-
-#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
@@ -29,6 +25,50 @@ impl Point {
             y: self.y + (next.y - self.y) * offset,
         }
     }
+    /// Cubic Hermite interpolation between two points, with Catmull-Rom tangents
+    /// taken from the points either side. `tangent_scale` scales the tangents at
+    /// this point and the next respectively.
+    pub fn catmull_rom(
+        &self,
+        previous: &Point,
+        next: &Point,
+        after: &Point,
+        tangent_scale: (f64, f64),
+        offset: f64,
+    ) -> Self {
+        let (t, t2, t3) = (offset, offset * offset, offset * offset * offset);
+        let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+        let h10 = t3 - 2.0 * t2 + t;
+        let h01 = -2.0 * t3 + 3.0 * t2;
+        let h11 = t3 - t2;
+        let m0 = (
+            (next.x - previous.x) * tangent_scale.0,
+            (next.y - previous.y) * tangent_scale.0,
+        );
+        let m1 = (
+            (after.x - self.x) * tangent_scale.1,
+            (after.y - self.y) * tangent_scale.1,
+        );
+        Point {
+            x: h00 * self.x + h10 * m0.0 + h01 * next.x + h11 * m1.0,
+            y: h00 * self.y + h10 * m0.1 + h01 * next.y + h11 * m1.1,
+        }
+    }
+    /// Straight line distance between two points
+    pub fn distance(&self, other: &Point) -> f64 {
+        (other.x - self.x).hypot(other.y - self.y)
+    }
+    /// Angle (radians, clockwise from the x axis) of the line from this point to another
+    pub fn angle_to(&self, other: &Point) -> f64 {
+        (other.y - self.y).atan2(other.x - self.x)
+    }
+    /// Point at a distance and angle (see [`Point::angle_to`]) from this point
+    pub fn polar_offset(&self, angle: f64, length: f64) -> Self {
+        Point {
+            x: self.x + angle.cos() * length,
+            y: self.y + angle.sin() * length,
+        }
+    }
     pub fn apply_offset_by(&self, initial_hip_coords: Point) -> Self {
         Point {
             x: self.x + initial_hip_coords.x,
@@ -37,35 +77,101 @@ impl Point {
     }
 }
 
+/// Colour of the lines a human is drawn with
+pub const LINE_COLOUR: &str = "black";
+/// Width of the lines a human is drawn with
+pub const LINE_WIDTH: f64 = 0.01;
+
 #[derive(Debug, Clone, Copy)]
 /// struct containing the position of the a corner and the b corner
-struct Position {
-    a: Point,
-    b: Point,
+pub struct Position {
+    pub a: Point,
+    pub b: Point,
 }
 
-// rendering a Position
+// drawing a Position
 impl Position {
-    fn render(&self) -> Html {
-        html! {
-            <>
-                <line x1={(self.a.x).to_string()} y1={(self.a.y).to_string()} x2={(self.b.x).to_string()} y2={(self.b.y).to_string()} stroke="black" stroke-width="0.01" />
-            </>
+    pub fn to_shape(&self, stroke: &Stroke) -> Shape {
+        Shape::Line {
+            a: self.a,
+            b: self.b,
+            stroke: stroke.clone(),
         }
     }
 }
 
-pub struct Viewport {
-    pub x0: f64,
-    pub y0: f64,
-    pub x1: f64,
-    pub y1: f64,
+/// Which way a figure faces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Facing {
+    Left,
+    Right,
+}
+
+/// The way figures face in recorded animations, which walk towards the left
+pub const RECORDED_FACING: Facing = Facing::Left;
+
+impl Facing {
+    /// Whether recorded poses have to be mirrored to face this way
+    pub fn is_mirrored(self) -> bool {
+        self != RECORDED_FACING
+    }
 }
 
+/// Milliseconds taken to blend from one animation into the next, unless changed
+const DEFAULT_CROSSFADE_DURATION: f64 = 300.0;
+
 pub struct Human {
     pub joints: animation::AnimationPosition,
-    /// The current animation and its start time
-    current_animation: Option<(&'static animation::Animation, f64, Point)>,
+    /// The current animation
+    current_animation: Option<PlayingAnimation>,
+    /// The previous animation, while it is being blended out
+    crossfade: Option<Crossfade>,
+    /// Milliseconds taken to blend from one animation into the next
+    pub crossfade_duration: f64,
+    /// Name of the animation to play when no other is playing
+    pub default_animation: String,
+    /// Where the figure is in the world, added to its joints when drawn.
+    /// Moved on by the root motion of its animations, which are played in place.
+    pub position: Point,
+    /// Size the figure is drawn at, about the scene's origin before it is placed
+    pub scale: f64,
+    /// Any CSS colour
+    pub colour: String,
+    /// Which way the figure faces, mirroring its animations if they were recorded
+    /// facing the other way
+    pub facing: Facing,
+    /// Milliseconds the figure's animations run ahead of the clock, so figures
+    /// playing the same animation are not in step
+    pub time_offset: f64,
+    /// Joints and position before the last update, for drawing between updates
+    previous: Option<(animation::AnimationPosition, Point)>,
+}
+
+/// An animation being played by a human
+struct PlayingAnimation {
+    animation: Rc<animation::Animation>,
+    start_time: f64,
+    /// Offset from the animation's positions to the human's
+    offset: Point,
+    /// Root motion of the animation as of the last update
+    travelled: f64,
+}
+
+impl PlayingAnimation {
+    fn step(&self, time: f64) -> Option<animation::AnimationPosition> {
+        self.animation
+            .step_in_place(time - self.start_time)
+            .map(|joints| joints.apply_offset_by(self.offset))
+    }
+}
+
+/// An animation being blended out in favour of the current one
+struct Crossfade {
+    from: PlayingAnimation,
+    start_time: f64,
+    /// The last position of the animation being blended out, held if it ends mid-fade
+    held: animation::AnimationPosition,
 }
 
 pub enum UpdateHuman {
@@ -102,6 +208,12 @@ impl Human {
     }
 }
 
+impl Default for Human {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Human {
     pub fn new() -> Self {
         Human {
@@ -152,31 +264,106 @@ impl Human {
                 },
             },
             current_animation: None,
+            crossfade: None,
+            crossfade_duration: DEFAULT_CROSSFADE_DURATION,
+            default_animation: "walking".to_string(),
+            position: Point { x: 0.0, y: 0.0 },
+            scale: 1.0,
+            colour: LINE_COLOUR.to_string(),
+            facing: RECORDED_FACING,
+            time_offset: 0.0,
+            previous: None,
         }
     }
-    pub fn update(&mut self, time: f64) {
-        if let Some((animation, start_time, offset)) = &self.current_animation {
-            if let Some(joints) = animation.step(time - start_time) {
-                self.joints = joints.apply_offset_by(*offset);
-            } else {
-                self.current_animation = None;
-            }
-        } else {
-            let animation = &ANIMATIONS_DATA.walking;
-            if let Some(first_pos) = animation.positions.get(0) {
-                let offset = Point { x: self.joints.hip.x - first_pos.hip.x, y: self.joints.hip.y - first_pos.hip.y }; 
-                self.current_animation = Some((
-                    animation,
-                    time,
-                    offset
-                ));
+    /// Start playing the named animation, blending from the current one.
+    /// Returns false if there is no animation with that name
+    pub fn transition(
+        &mut self,
+        animations: &animation::AnimationsData,
+        name: &str,
+        time: f64,
+    ) -> bool {
+        let animation = match animations.get(name) {
+            Some(animation) => animation,
+            None => return false,
+        };
+        let first_hip = match animation.keyframes.first() {
+            Some(first) => first.position.hip,
+            None => return false,
+        };
+        // start the new animation from wherever the human currently is
+        let offset = Point {
+            x: self.joints.hip.x - first_hip.x,
+            y: self.joints.hip.y - first_hip.y,
+        };
+        // started at the clock's time, so it plays `time_offset` into the animation,
+        // and has already travelled as far as it would have by then
+        let travelled = animation.root_motion(self.time_offset);
+        let next = PlayingAnimation {
+            animation,
+            start_time: time,
+            offset,
+            travelled,
+        };
+        // the fade is timed the way the animations are played
+        let time = time + self.time_offset;
+        let previous = self.current_animation.replace(next);
+        self.crossfade = previous
+            .filter(|_| self.crossfade_duration > 0.0)
+            .map(|from| Crossfade {
+                from,
+                start_time: time,
+                held: self.joints.clone(),
+            });
+        true
+    }
+    /// Move on to the clock's time
+    pub fn update(&mut self, animations: &animation::AnimationsData, clock: &Clock) {
+        let clock_time = clock.time();
+        let time = clock_time + self.time_offset;
+        self.previous = Some((self.joints.clone(), self.position));
+        let joints = match self.current_animation.as_ref().map(|a| a.step(time)) {
+            Some(Some(joints)) => joints,
+            // the animation ended (or none was playing), so go back to the default
+            _ => {
+                let default_animation = self.default_animation.clone();
+                if !self.transition(animations, &default_animation, clock_time) {
+                    return;
+                }
+                match self.current_animation.as_ref().and_then(|a| a.step(time)) {
+                    Some(joints) => joints,
+                    None => return,
+                }
             }
+        };
+        // move through the world as far as the animation moved since the last update,
+        // the other way if the pose is mirrored
+        if let Some(playing) = &mut self.current_animation {
+            let travelled = playing.animation.root_motion(time - playing.start_time);
+            let direction = if self.facing.is_mirrored() { -1.0 } else { 1.0 };
+            self.position.x += (travelled - playing.travelled) * direction * self.scale;
+            playing.travelled = travelled;
         }
+        self.joints = match &mut self.crossfade {
+            Some(fade) => {
+                let progress = (time - fade.start_time) / self.crossfade_duration;
+                if progress >= 1.0 {
+                    self.crossfade = None;
+                    joints
+                } else {
+                    if let Some(from) = fade.from.step(time) {
+                        fade.held = from;
+                    }
+                    fade.held.lerp(&joints, progress)
+                }
+            }
+            None => joints,
+        };
     }
 
-    pub fn view(&self) -> Html {
+    /// The lines a human with the given joints is drawn with, in drawing order
+    pub fn lines(joints: &animation::AnimationPosition) -> [Position; 10] {
         let to_pos = |a, b| Position { a, b };
-        let joints = &self.joints;
         let (
             left_calf,
             left_thigh,
@@ -201,19 +388,163 @@ impl Human {
             to_pos(joints.neck, joints.head),
         );
 
-        html! {
-            <>
-                {left_calf.render()}
-                {left_thigh.render()}
-                {left_forearm.render()}
-                {left_bicep.render()}
-                {right_calf.render()}
-                {right_thigh.render()}
-                {right_forearm.render()}
-                {right_bicep.render()}
-                {torso.render()}
-                {head.render()}
-            </>
+        [
+            left_calf,
+            left_thigh,
+            left_forearm,
+            left_bicep,
+            right_calf,
+            right_thigh,
+            right_forearm,
+            right_bicep,
+            torso,
+            head,
+        ]
+    }
+
+    /// The animation being played and how far into it the clock's time is
+    pub fn playback(&self, clock: &Clock) -> Option<(Rc<animation::Animation>, f64)> {
+        let playing = self.current_animation.as_ref()?;
+        let time = clock.time() + self.time_offset - playing.start_time;
+        Some((playing.animation.clone(), time))
+    }
+    /// Joints as drawn, scaled and moved to the human's position
+    pub fn placed_joints(&self) -> animation::AnimationPosition {
+        self.placed_joints_at(1.0)
+    }
+    /// Joints as drawn a fraction `alpha` of the way from before the last update to
+    /// after it, so movement looks smooth when drawing more often than updating
+    pub fn placed_joints_at(&self, alpha: f64) -> animation::AnimationPosition {
+        let (joints, position) = match &self.previous {
+            Some((joints, position)) if alpha < 1.0 => (
+                joints.lerp(&self.joints, alpha),
+                position.lerp(&self.position, alpha),
+            ),
+            _ => (self.joints.clone(), self.position),
+        };
+        joints.map(|joint| Point {
+            x: position.x + joint.x * self.scale,
+            y: position.y + joint.y * self.scale,
+        })
+    }
+    /// Add the lines of the human to a scene
+    pub fn draw(&self, scene: &mut Scene) {
+        self.draw_at(scene, 1.0);
+    }
+    /// Add the lines of the human to a scene, between its last two updates
+    /// (see [`Human::placed_joints_at`])
+    pub fn draw_at(&self, scene: &mut Scene, alpha: f64) {
+        let stroke = Stroke {
+            colour: self.colour.clone(),
+            width: LINE_WIDTH * self.scale,
+        };
+        Human::draw_joints(&self.placed_joints_at(alpha), self.facing, &stroke, scene);
+    }
+    /// Add the lines of a human with the given joints, recorded facing
+    /// [`RECORDED_FACING`], to a scene
+    pub fn draw_joints(
+        joints: &animation::AnimationPosition,
+        facing: Facing,
+        stroke: &Stroke,
+        scene: &mut Scene,
+    ) {
+        for line in Human::facing_lines(joints, facing).iter() {
+            scene.push(line.to_shape(stroke));
         }
     }
+    /// The lines a human with the given joints is drawn with when facing a direction,
+    /// in drawing order. Mirrored poses are flipped about the hip, and their right
+    /// limbs drawn first as they are now the furthest away.
+    pub fn facing_lines(joints: &animation::AnimationPosition, facing: Facing) -> [Position; 10] {
+        if !facing.is_mirrored() {
+            return Human::lines(joints);
+        }
+        let mut lines = Human::lines(&joints.mirrored());
+        // right limbs then left limbs, then the torso and head
+        lines[..8].rotate_left(4);
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::clock::STEP;
+
+    fn drawn_lines(human: &Human) -> Vec<(Point, Point, Stroke)> {
+        let mut scene = Scene::new(Camera::new().viewport());
+        human.draw(&mut scene);
+        let lines = scene.lines();
+        lines.map(|(a, b, stroke)| (a, b, stroke.clone())).collect()
+    }
+
+    #[test]
+    fn time_offset_puts_figures_out_of_step() {
+        let animations =
+            animation::AnimationsData::embedded().expect("the walk cycle should parse");
+        let mut clock = Clock::new();
+        let mut humans = [Human::new(), Human::new()];
+        humans[1].time_offset = 700.0;
+        clock.tick(0.0);
+        for tick in 1..=120 {
+            clock.tick(tick as f64 * STEP);
+            while clock.step() {
+                for human in &mut humans {
+                    human.update(&animations, &clock);
+                }
+            }
+        }
+        assert_ne!(humans[0].joints, humans[1].joints);
+        let playback = |human: &Human| human.playback(&clock).map(|(_, time)| time);
+        let (first, second) = (playback(&humans[0]), playback(&humans[1]));
+        assert!((second.unwrap() - first.unwrap() - 700.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn draws_a_line_between_joints_placed_and_scaled() {
+        let mut human = Human::new();
+        human.position = Point { x: 1.0, y: 2.0 };
+        human.scale = 2.0;
+        let placed = |joint: Point| Point {
+            x: 1.0 + joint.x * 2.0,
+            y: 2.0 + joint.y * 2.0,
+        };
+        let joints = &human.joints;
+        let stroke = Stroke {
+            colour: LINE_COLOUR.to_string(),
+            width: LINE_WIDTH * 2.0,
+        };
+        let lines = drawn_lines(&human);
+        assert_eq!(lines.len(), 10);
+        assert_eq!(
+            lines[0],
+            (
+                placed(joints.left_foot),
+                placed(joints.left_knee),
+                stroke.clone()
+            )
+        );
+        assert_eq!(lines[9], (placed(joints.neck), placed(joints.head), stroke));
+    }
+
+    #[test]
+    fn mirrored_figure_draws_its_right_limbs_first() {
+        let mut human = Human::new();
+        human.facing = Facing::Right;
+        assert!(human.facing.is_mirrored());
+        let mirrored = human.joints.mirrored();
+        let lines = Human::facing_lines(&human.joints, human.facing);
+        let ends = |line: &Position| (line.a, line.b);
+        // right calf, thigh, forearm, bicep, then the left ones
+        assert_eq!(ends(&lines[0]), (mirrored.right_knee, mirrored.right_foot));
+        assert_eq!(ends(&lines[1]), (mirrored.hip, mirrored.right_knee));
+        assert_eq!(ends(&lines[4]), (mirrored.left_foot, mirrored.left_knee));
+        assert_eq!(ends(&lines[8]), (mirrored.hip, mirrored.neck));
+        assert_eq!(ends(&lines[9]), (mirrored.neck, mirrored.head));
+        // flipped about the hip
+        assert_eq!(mirrored.hip, human.joints.hip);
+        let drawn = drawn_lines(&human);
+        assert_eq!((drawn[0].0, drawn[0].1), ends(&lines[0]));
+    }
 }
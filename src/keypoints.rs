@@ -0,0 +1,378 @@
+use crate::animation::{Animation, AnimationPosition};
+use crate::human::Point;
+use crate::loader::LoadError;
+
+#[allow(unused_imports)]
+use crate::trace;
+
+/// Size of the area poses are placed in, matching the viewport of the tester
+const VIEWPORT_WIDTH: f64 = 1.5;
+const VIEWPORT_HEIGHT: f64 = 1.0;
+/// Fraction of the viewport height a pose of unknown image size is scaled to fill
+const FIT_HEIGHT: f64 = 0.8;
+
+/// One frame of OpenPose output
+#[derive(serde::Deserialize)]
+struct OpenPoseFrame {
+    people: Vec<OpenPosePerson>,
+}
+
+#[derive(serde::Deserialize)]
+struct OpenPosePerson {
+    /// x, y and confidence of each keypoint
+    pose_keypoints_2d: Vec<f64>,
+}
+
+/// COCO keypoint annotations, either a whole dataset or a list of detection results
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum CocoFile {
+    Dataset {
+        images: Vec<CocoImage>,
+        annotations: Vec<CocoAnnotation>,
+    },
+    Results(Vec<CocoAnnotation>),
+}
+
+#[derive(serde::Deserialize)]
+struct CocoImage {
+    id: u64,
+    width: f64,
+    height: f64,
+    #[serde(default)]
+    file_name: String,
+}
+
+#[derive(serde::Deserialize)]
+struct CocoAnnotation {
+    image_id: u64,
+    /// x, y and visibility (or confidence) of each keypoint
+    keypoints: Vec<f64>,
+}
+
+/// Keypoint orderings used by pose estimators
+#[derive(Debug, Clone, Copy)]
+enum Layout {
+    /// OpenPose BODY_25
+    Body25,
+    /// OpenPose COCO, which adds a neck to COCO-17
+    Coco18,
+    /// COCO-17
+    Coco17,
+}
+
+/// Where a joint of the stick figure comes from in a keypoint layout
+#[derive(Clone, Copy)]
+enum Source {
+    Keypoint(usize),
+    /// Halfway between two keypoints
+    Midpoint(usize, usize),
+}
+
+impl Layout {
+    /// Work out the layout from the number of values (three per keypoint)
+    fn from_len(len: usize) -> Option<Layout> {
+        match len {
+            75 => Some(Layout::Body25),
+            54 => Some(Layout::Coco18),
+            51 => Some(Layout::Coco17),
+            _ => None,
+        }
+    }
+    /// Sources of each joint, in the order of the fields of `AnimationPosition`.
+    /// Shoulders are merged into the neck and ankles and wrists become feet and hands.
+    fn sources(self) -> [Source; 11] {
+        use Source::*;
+        match self {
+            Layout::Body25 => [
+                Keypoint(14),
+                Keypoint(13),
+                Keypoint(8),
+                Keypoint(10),
+                Keypoint(11),
+                Keypoint(1),
+                Keypoint(6),
+                Keypoint(7),
+                Keypoint(3),
+                Keypoint(4),
+                Keypoint(0),
+            ],
+            Layout::Coco18 => [
+                Keypoint(13),
+                Keypoint(12),
+                Midpoint(8, 11),
+                Keypoint(9),
+                Keypoint(10),
+                Keypoint(1),
+                Keypoint(6),
+                Keypoint(7),
+                Keypoint(3),
+                Keypoint(4),
+                Keypoint(0),
+            ],
+            Layout::Coco17 => [
+                Keypoint(15),
+                Keypoint(13),
+                Midpoint(11, 12),
+                Keypoint(14),
+                Keypoint(16),
+                Midpoint(5, 6),
+                Keypoint(7),
+                Keypoint(9),
+                Keypoint(8),
+                Keypoint(10),
+                Keypoint(0),
+            ],
+        }
+    }
+}
+
+/// Joints read from one frame, in image pixels. Joints that were not detected are none.
+struct RawPose {
+    joints: [Option<Point>; 11],
+    /// Width and height of the image the keypoints were detected in, if known
+    image_size: Option<(f64, f64)>,
+}
+
+impl RawPose {
+    fn from_keypoints(keypoints: &[f64], image_size: Option<(f64, f64)>) -> Option<RawPose> {
+        let layout = Layout::from_len(keypoints.len())?;
+        let keypoint = |i: usize| {
+            let (x, y, confidence) = (keypoints[3 * i], keypoints[3 * i + 1], keypoints[3 * i + 2]);
+            if confidence > 0.0 {
+                Some(Point { x, y })
+            } else {
+                None
+            }
+        };
+        Some(RawPose {
+            joints: layout.sources().map(|source| match source {
+                Source::Keypoint(i) => keypoint(i),
+                Source::Midpoint(a, b) => Some(keypoint(a)?.lerp(&keypoint(b)?, 0.5)),
+            }),
+            image_size,
+        })
+    }
+    /// A frame where nobody was detected, filled in from its neighbours later
+    fn empty(image_size: Option<(f64, f64)>) -> RawPose {
+        RawPose {
+            joints: [None; 11],
+            image_size,
+        }
+    }
+    fn detected(&self) -> usize {
+        self.joints.iter().filter(|j| j.is_some()).count()
+    }
+}
+
+/// Read the poses in a keypoint file, one per frame in frame order.
+/// Where a frame has several people, the one with the most detected joints is used,
+/// and where it has none, a pose with no joints detected.
+fn parse_file(file: &str, bytes: &[u8]) -> Result<Vec<RawPose>, LoadError> {
+    let parse_error = |reason: String| LoadError::Parse(file.to_string(), reason);
+    let value: serde_json::Value =
+        serde_json::from_slice(bytes).map_err(|e| parse_error(e.to_string()))?;
+
+    if value.get("people").is_some() {
+        let frame: OpenPoseFrame =
+            serde_json::from_value(value).map_err(|e| parse_error(e.to_string()))?;
+        let pose = frame
+            .people
+            .iter()
+            .filter_map(|person| RawPose::from_keypoints(&person.pose_keypoints_2d, None))
+            .max_by_key(RawPose::detected)
+            .unwrap_or_else(|| RawPose::empty(None));
+        return Ok(vec![pose]);
+    }
+
+    let (mut images, annotations) =
+        match serde_json::from_value(value).map_err(|e| parse_error(e.to_string()))? {
+            CocoFile::Dataset {
+                images,
+                annotations,
+            } => (images, annotations),
+            CocoFile::Results(annotations) => {
+                let mut ids: Vec<u64> = annotations.iter().map(|a| a.image_id).collect();
+                ids.sort_unstable();
+                ids.dedup();
+                let images = ids
+                    .into_iter()
+                    .map(|id| CocoImage {
+                        id,
+                        width: 0.0,
+                        height: 0.0,
+                        file_name: String::new(),
+                    })
+                    .collect();
+                (images, annotations)
+            }
+        };
+    images.sort_by(|a, b| a.file_name.cmp(&b.file_name).then(a.id.cmp(&b.id)));
+    Ok(images
+        .iter()
+        .map(|image| {
+            let image_size =
+                Some((image.width, image.height)).filter(|(w, h)| *w > 0.0 && *h > 0.0);
+            annotations
+                .iter()
+                .filter(|a| a.image_id == image.id)
+                .filter_map(|a| RawPose::from_keypoints(&a.keypoints, image_size))
+                .max_by_key(RawPose::detected)
+                .unwrap_or_else(|| RawPose::empty(image_size))
+        })
+        .collect())
+}
+
+/// Convert OpenPose or COCO keypoint JSON into an animation with a keyframe per frame.
+/// OpenPose writes a file per frame, so files should be given in frame order.
+///
+/// Joints that were not detected in a frame are taken from the nearest frame they were.
+/// Poses are placed in the viewport the way the tester shows the image they were detected
+/// in, or scaled to fit the viewport if the image size is unknown.
+pub fn import_keypoints(files: &[(String, Vec<u8>)]) -> Result<Animation, LoadError> {
+    let mut poses = vec![];
+    for (file, bytes) in files {
+        poses.extend(parse_file(file, bytes)?);
+    }
+    let file = files.first().map_or("", |(file, _)| file);
+    if poses.is_empty() {
+        return Err(LoadError::Parse(
+            file.to_string(),
+            "no poses found".to_string(),
+        ));
+    }
+    // there would be nothing to fill the frames from
+    if poses.iter().all(|pose| pose.detected() == 0) {
+        return Err(LoadError::Parse(
+            file.to_string(),
+            "no joints detected".to_string(),
+        ));
+    }
+
+    // fill undetected joints from the previous frame, then from the next
+    for i in 1..poses.len() {
+        for joint in 0..11 {
+            if poses[i].joints[joint].is_none() {
+                poses[i].joints[joint] = poses[i - 1].joints[joint];
+            }
+        }
+    }
+    for i in (0..poses.len() - 1).rev() {
+        for joint in 0..11 {
+            if poses[i].joints[joint].is_none() {
+                poses[i].joints[joint] = poses[i + 1].joints[joint];
+            }
+        }
+    }
+
+    let to_viewport = viewport_transform(&poses);
+    let mut animation = Animation::new();
+    for pose in &poses {
+        // joints that were never detected collapse onto the hip
+        let hip = pose.joints[2].unwrap_or(Point { x: 0.0, y: 0.0 });
+        let [left_foot, left_knee, hip, right_knee, right_foot, neck, left_elbow, left_hand, right_elbow, right_hand, head] =
+            pose.joints
+                .map(|joint| to_viewport(pose, joint.unwrap_or(hip)));
+        animation.push(AnimationPosition {
+            left_foot,
+            left_knee,
+            hip,
+            right_knee,
+            right_foot,
+            neck,
+            left_elbow,
+            left_hand,
+            right_elbow,
+            right_hand,
+            head,
+        });
+    }
+    Ok(animation)
+}
+
+/// Mapping from image pixels to the viewport
+fn viewport_transform(poses: &[RawPose]) -> impl Fn(&RawPose, Point) -> Point {
+    // bounding box of every joint, for poses without an image size
+    let points = poses.iter().flat_map(|p| p.joints.iter().flatten());
+    let (mut min, mut max) = (
+        Point {
+            x: f64::INFINITY,
+            y: f64::INFINITY,
+        },
+        Point {
+            x: f64::NEG_INFINITY,
+            y: f64::NEG_INFINITY,
+        },
+    );
+    for point in points {
+        min = Point {
+            x: min.x.min(point.x),
+            y: min.y.min(point.y),
+        };
+        max = Point {
+            x: max.x.max(point.x),
+            y: max.y.max(point.y),
+        };
+    }
+    let fit_scale = FIT_HEIGHT * VIEWPORT_HEIGHT / (max.y - min.y).max(f64::EPSILON);
+
+    move |pose, point| match pose.image_size {
+        // letterbox the image into the viewport, as the tester does
+        Some((width, height)) => {
+            let scale = (VIEWPORT_WIDTH / width).min(VIEWPORT_HEIGHT / height);
+            Point {
+                x: point.x * scale + (VIEWPORT_WIDTH - width * scale) / 2.0,
+                y: point.y * scale + (VIEWPORT_HEIGHT - height * scale) / 2.0,
+            }
+        }
+        // centre the bounding box
+        None => Point {
+            x: (point.x - (min.x + max.x) / 2.0) * fit_scale + VIEWPORT_WIDTH / 2.0,
+            y: (point.y - (min.y + max.y) / 2.0) * fit_scale + VIEWPORT_HEIGHT / 2.0,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_pose_frame(confidence: &str) -> Vec<u8> {
+        let keypoint = format!("0.5, 0.5, {}", confidence);
+        let keypoints = vec![keypoint.as_str(); 25].join(", ");
+        format!(
+            r#"{{"people": [{{"pose_keypoints_2d": [{}]}}]}}"#,
+            keypoints
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn frames_without_anyone_keep_their_place() {
+        let files = [
+            ("frame_0.json".to_string(), open_pose_frame("1")),
+            ("frame_1.json".to_string(), br#"{"people": []}"#.to_vec()),
+            ("frame_2.json".to_string(), open_pose_frame("1")),
+        ];
+        let animation = import_keypoints(&files).expect("two frames have a pose");
+        let times: Vec<f64> = animation.keyframes.iter().map(|k| k.time).collect();
+        assert_eq!(times, [0.0, 200.0, 400.0]);
+        let first = &animation.keyframes[0].position;
+        assert!(animation.keyframes.iter().all(|k| &k.position == first));
+    }
+
+    #[test]
+    fn nothing_detected_is_an_error() {
+        let files = [
+            ("frame_0.json".to_string(), open_pose_frame("0")),
+            ("frame_1.json".to_string(), br#"{"people": []}"#.to_vec()),
+        ];
+        match import_keypoints(&files) {
+            Err(LoadError::Parse(file, reason)) => {
+                assert_eq!(file, "frame_0.json");
+                assert_eq!(reason, "no joints detected");
+            }
+            other => panic!("expected a parse error, got {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+//! Stick figure animation, shared by the web app and the native renderer.
+//! Everything touching the browser is behind the `web` feature.
+
+pub mod animation;
+pub mod background;
+pub mod camera;
+#[cfg(feature = "web")]
+pub mod canvas;
+pub mod clock;
+pub mod export;
+#[cfg(feature = "web")]
+pub mod footage;
+pub mod format;
+pub mod history;
+pub mod human;
+pub mod keypoints;
+pub mod loader;
+pub mod raster;
+pub mod scene;
+pub mod skeleton;
+pub mod stage;
+#[cfg(feature = "web")]
+pub mod svg;
+#[cfg(feature = "web")]
+pub mod tester;
+#[cfg(feature = "web")]
+pub mod timeline;
+
+// Allow other modules to use the logging macro
+// use print;
+// Logging macro for easier debugging. Displays file and line number. Use with `log!("Hello World")`
+#[allow(unused_macros)]
+macro_rules! trace {
+    ($($t:tt)*) => {
+        log::debug!("[{}:{}] {}", file!(), line!(), &format_args!($($t)*).to_string());
+    }
+}
+pub(crate) use trace;
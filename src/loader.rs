@@ -0,0 +1,134 @@
+use crate::animation::Animation;
+#[cfg(feature = "web")]
+use crate::animation::AnimationsData;
+use crate::format::Encoding;
+#[cfg(feature = "web")]
+use crate::stage::{Stage, StageFile};
+use std::fmt;
+#[cfg(feature = "web")]
+use wasm_bindgen::JsCast;
+#[cfg(feature = "web")]
+use wasm_bindgen_futures::JsFuture;
+
+#[allow(unused_imports)]
+use crate::trace;
+
+/// Location of the manifest listing the animations to load at startup
+pub const MANIFEST_URL: &str = "./public/animations/index.json";
+/// Location of the figures to place on the stage at startup
+pub const STAGE_URL: &str = "./public/stage.json";
+
+/// List of animation files, relative to the manifest
+#[cfg(feature = "web")]
+#[derive(Debug, serde::Deserialize)]
+struct Manifest {
+    animations: Vec<ManifestEntry>,
+}
+
+#[cfg(feature = "web")]
+#[derive(Debug, serde::Deserialize)]
+struct ManifestEntry {
+    name: String,
+    file: String,
+}
+
+/// Reasons loading animations can fail
+#[derive(Debug)]
+pub enum LoadError {
+    /// Requesting a file failed (url and reason)
+    Fetch(String, String),
+    /// A file could not be parsed (file and reason)
+    Parse(String, String),
+    /// A file is not in a format animations can be read from
+    UnknownFormat(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Fetch(url, reason) => write!(f, "unable to fetch {}: {}", url, reason),
+            LoadError::Parse(file, reason) => write!(f, "unable to parse {}: {}", file, reason),
+            LoadError::UnknownFormat(file) => write!(f, "unknown animation format for {}", file),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+#[cfg(feature = "web")]
+impl AnimationsData {
+    /// Fetch the manifest and every animation listed in it
+    pub async fn fetch(manifest_url: &str) -> Result<AnimationsData, LoadError> {
+        let bytes = fetch_bytes(manifest_url).await?;
+        let manifest: Manifest = serde_json::from_slice(&bytes)
+            .map_err(|e| LoadError::Parse(manifest_url.to_string(), e.to_string()))?;
+        // files are listed relative to the directory of the manifest
+        let directory = match manifest_url.rfind('/') {
+            Some(index) => &manifest_url[..=index],
+            None => "",
+        };
+
+        let mut animations = AnimationsData::default();
+        for entry in manifest.animations {
+            let url = format!("{}{}", directory, entry.file);
+            let bytes = fetch_bytes(&url).await?;
+            animations.insert(&entry.name, parse_animation(&url, &bytes)?);
+        }
+        trace!("loaded animations from {}", manifest_url);
+        Ok(animations)
+    }
+}
+
+/// Fetch a stage file and place its figures
+#[cfg(feature = "web")]
+pub async fn fetch_stage(url: &str) -> Result<Stage, LoadError> {
+    let bytes = fetch_bytes(url).await?;
+    let file: StageFile = serde_json::from_slice(&bytes)
+        .map_err(|e| LoadError::Parse(url.to_string(), e.to_string()))?;
+    Ok(Stage::from_file(file))
+}
+
+/// Parse an animation in any encoding, detected from its file extension or contents
+pub fn parse_animation(file: &str, bytes: &[u8]) -> Result<Animation, LoadError> {
+    Encoding::detect(file, bytes)
+        .ok_or_else(|| LoadError::UnknownFormat(file.to_string()))?
+        .decode(bytes)
+        .map_err(|e| LoadError::Parse(file.to_string(), e))
+}
+
+/// Read an animation from a file the user picked
+#[cfg(feature = "web")]
+pub async fn read_file(file: web_sys::File) -> Result<Animation, LoadError> {
+    parse_animation(&file.name(), &read_bytes(&file).await?)
+}
+
+/// Read the whole of a file the user picked
+#[cfg(feature = "web")]
+pub async fn read_bytes(file: &web_sys::File) -> Result<Vec<u8>, LoadError> {
+    let buffer = JsFuture::from(file.array_buffer())
+        .await
+        .map_err(|e| LoadError::Fetch(file.name(), format!("{:?}", e)))?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+/// Request a file and read the whole response
+#[cfg(feature = "web")]
+async fn fetch_bytes(url: &str) -> Result<Vec<u8>, LoadError> {
+    let fetch_error = |reason: String| LoadError::Fetch(url.to_string(), reason);
+    let window = web_sys::window().expect("no global `window` exists");
+    let response = JsFuture::from(window.fetch_with_str(url))
+        .await
+        .map_err(|e| fetch_error(format!("{:?}", e)))?
+        .dyn_into::<web_sys::Response>()
+        .expect("fetch should resolve to a response");
+    if !response.ok() {
+        return Err(fetch_error(format!("status {}", response.status())));
+    }
+    let buffer = response
+        .array_buffer()
+        .map_err(|e| fetch_error(format!("{:?}", e)))?;
+    let buffer = JsFuture::from(buffer)
+        .await
+        .map_err(|e| fetch_error(format!("{:?}", e)))?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
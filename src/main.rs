@@ -1,32 +1,115 @@
 use gloo_render::{request_animation_frame, AnimationFrame};
 use log::Level;
 use yew::prelude::*;
-#[macro_use]
-extern crate lazy_static;
+#[allow(unused_imports)]
+use yew_app::tester;
+use yew_app::{animation, camera, canvas, clock, loader, scene, stage, svg};
 
-mod animation;
-mod human;
-mod tester;
-mod background;
-
-// Allow other modules to use the logging macro
-// use print;
-// Logging macro for easier debugging. Displays file and line number. Use with `log!("Hello World")`
-macro_rules! trace {
-    ($($t:tt)*) => {
-        log::debug!("[{}:{}] {}", file!(), line!(), &format_args!($($t)*).to_string());
-    }
-}
-pub(crate) use trace;
+/// Playback speeds offered in the transport bar
+const SPEEDS: [f64; 6] = [0.1, 0.25, 0.5, 1.0, 2.0, 4.0];
 
 enum Msg {
     Tick(f64),
+    TogglePlay,
+    SetSpeed(f64),
+    /// Move to a time within the current repetition of the followed figure's animation
+    Scrub(f64),
+    /// Move to the next keyframe, or the previous one if false
+    StepKeyframe(bool),
+    Loaded(Result<animation::AnimationsData, loader::LoadError>),
+    StageLoaded(Result<stage::Stage, loader::LoadError>),
+}
+
+/// How the scene is drawn, chosen at startup with `?renderer=canvas` or `?renderer=svg`
+enum Renderer {
+    /// Svg elements, rebuilt through the virtual DOM each frame
+    Svg,
+    /// Drawn straight onto a canvas each frame, outside the virtual DOM
+    Canvas(canvas::CanvasRenderer, NodeRef),
+}
+
+impl Renderer {
+    /// The renderer asked for in the page's query string, svg if none is
+    fn from_query() -> Self {
+        let search = web_sys::window()
+            .and_then(|window| window.location().search().ok())
+            .unwrap_or_default();
+        let canvas = search
+            .trim_start_matches('?')
+            .split('&')
+            .any(|pair| pair == "renderer=canvas");
+        if canvas {
+            Renderer::Canvas(canvas::CanvasRenderer::new(), NodeRef::default())
+        } else {
+            Renderer::Svg
+        }
+    }
 }
 
 struct Model {
-    human: human::Human,
-    background: background::Background,
+    stage: stage::Stage,
+    camera: camera::Camera,
+    clock: clock::Clock,
     animation_id: Option<AnimationFrame>,
+    /// None while the animations are being fetched
+    animations: Option<animation::AnimationsData>,
+    renderer: Renderer,
+}
+
+impl Model {
+    fn scene(&self) -> scene::Scene {
+        // the camera between its last two steps too, so the figures do not jitter against it
+        let alpha = self.clock.alpha();
+        let mut scene = scene::Scene::new(self.camera.viewport_at(alpha));
+        self.stage.draw(&mut scene, alpha);
+        scene
+    }
+    /// The animation the first figure is playing, and how far into it playback is
+    fn playback(&self) -> Option<(std::rc::Rc<animation::Animation>, f64)> {
+        self.stage.humans.first()?.playback(&self.clock)
+    }
+    /// Move playback by a time, updating the figures straight away
+    fn seek_by(&mut self, time: f64) {
+        self.clock.seek(self.clock.time() + time);
+        if let Some(animations) = &self.animations {
+            // twice, so there is nothing to draw between
+            self.stage.update(animations, &self.clock);
+            self.stage.update(animations, &self.clock);
+        }
+    }
+    fn transport_bar(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let (time, cycle) = match self.playback() {
+            Some((animation, time)) if animation.cycle() > 0.0 => {
+                (time.rem_euclid(animation.cycle()), animation.cycle())
+            }
+            _ => (0.0, 0.0),
+        };
+        let scrub = link.callback(|e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            Msg::Scrub(input.value_as_number())
+        });
+        let set_speed = link.batch_callback(|e: Event| {
+            let select: web_sys::HtmlSelectElement = e.target_unchecked_into();
+            select.value().parse().ok().map(Msg::SetSpeed)
+        });
+        html! {
+            <div class="controls">
+                <button onclick={link.callback(|_| Msg::StepKeyframe(false))}>{ "\u{23ee}" }</button>
+                <button onclick={link.callback(|_| Msg::TogglePlay)}>
+                    { if self.clock.is_paused() { "\u{25b6}" } else { "\u{23f8}" } }
+                </button>
+                <button onclick={link.callback(|_| Msg::StepKeyframe(true))}>{ "\u{23ed}" }</button>
+                <input type="range" min="0" max={cycle.to_string()} step="any" value={time.to_string()} oninput={scrub} />
+                <select onchange={set_speed}>
+                    { for SPEEDS.iter().map(|speed| html! {
+                        <option value={speed.to_string()} selected={*speed == self.clock.speed}>{ format!("{}\u{d7}", speed) }</option>
+                    }) }
+                </select>
+                <span>{ format!(" {:.0} / {:.0} ms", time, cycle) }</span>
+            </div>
+        }
+    }
 }
 
 impl Component for Model {
@@ -36,41 +119,117 @@ impl Component for Model {
     fn create(ctx: &Context<Self>) -> Self {
         let callback = ctx.link().callback(Msg::Tick);
         let request_id = request_animation_frame(move |t: f64| callback.emit(t));
+        ctx.link().send_future(async {
+            Msg::Loaded(animation::AnimationsData::fetch(loader::MANIFEST_URL).await)
+        });
+        ctx.link()
+            .send_future(async { Msg::StageLoaded(loader::fetch_stage(loader::STAGE_URL).await) });
         print!("create");
         Self {
-            human: human::Human::new(),
-            background: background::Background::new(),
+            stage: stage::Stage::single(),
+            camera: camera::Camera::new(),
+            clock: clock::Clock::new(),
             animation_id: Some(request_id),
+            animations: None,
+            renderer: Renderer::from_query(),
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::Tick(t) => {
-                self.human.update(t);
-                self.background.apply_offset(&self.human.joints);
+                // simulate in fixed steps, however often frames come
+                self.clock.tick(t);
+                while self.clock.step() {
+                    if let Some(animations) = &self.animations {
+                        self.stage.update(animations, &self.clock);
+                    }
+                    // follow the first figure as it moves through the world
+                    if let Some(human) = self.stage.humans.first() {
+                        self.camera
+                            .follow(human.placed_joints().hip, self.clock.time());
+                    }
+                }
 
                 let callback = ctx.link().callback(Msg::Tick);
                 let request_id = request_animation_frame(move |t: f64| callback.emit(t));
                 self.animation_id = Some(request_id);
+                // svg builds its own scene when the view is rendered
+                if matches!(self.renderer, Renderer::Canvas(..)) {
+                    let scene = self.scene();
+                    if let Renderer::Canvas(renderer, node) = &mut self.renderer {
+                        if let Some(canvas) = node.cast::<web_sys::HtmlCanvasElement>() {
+                            renderer.draw(&canvas, &scene);
+                        }
+                    }
+                }
+                // rerender for the transport bar, which is all that changes with a canvas
+                true
+            }
+            Msg::TogglePlay => {
+                self.clock.set_paused(!self.clock.is_paused());
+                true
+            }
+            Msg::SetSpeed(speed) => {
+                self.clock.speed = speed;
+                true
+            }
+            Msg::Scrub(time) => {
+                if let Some((animation, current)) = self.playback() {
+                    let cycle = animation.cycle();
+                    if cycle > 0.0 {
+                        self.seek_by(time - current.rem_euclid(cycle));
+                    }
+                }
+                true
+            }
+            Msg::StepKeyframe(forward) => {
+                self.clock.set_paused(true);
+                let step = self.playback().and_then(|(animation, current)| {
+                    Some(animation.adjacent_keyframe_time(current, forward)? - current)
+                });
+                if let Some(time) = step {
+                    self.seek_by(time);
+                }
+                true
+            }
+            Msg::Loaded(animations) => {
+                let animations = animations.or_else(|e| {
+                    log::error!("{}, falling back to the embedded walk cycle", e);
+                    animation::AnimationsData::embedded()
+                });
+                self.animations = Some(animations.unwrap_or_else(|e| {
+                    log::error!("{}", e);
+                    animation::AnimationsData::default()
+                }));
+                true
+            }
+            Msg::StageLoaded(stage) => {
+                match stage {
+                    Ok(stage) => self.stage = stage,
+                    Err(e) => log::error!("{}, keeping a single figure", e),
+                }
                 true
             }
         }
     }
 
-    fn view(&self, _ctx: &Context<Self>) -> Html {
-        let viewport = human::Viewport {
-            x0: 0.0,
-            y0: 0.0,
-            x1: 1.5,
-            y1: 1.0,
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        if self.animations.is_none() {
+            return html! {
+                <p>{ "Loading animations..." }</p>
+            };
+        }
+        let scene = match &self.renderer {
+            Renderer::Svg => svg::view(&self.scene(), "svg-container"),
+            Renderer::Canvas(_, node) => html! {
+                <canvas ref={node.clone()} class="canvas-container" />
+            },
         };
         html! {
             <div>
-                <svg viewBox={format!("{} {} {} {}", viewport.x0, viewport.y0, viewport.x1, viewport.y1)} class="svg-container">
-                    { self.background.view() }
-                    { self.human.view() }
-                </svg>
+                { self.transport_bar(ctx) }
+                { scene }
             </div>
         }
     }
@@ -0,0 +1,135 @@
+use crate::animation::{Animation, PlaybackMode};
+use crate::camera::Viewport;
+use crate::human::{self, Human, Point};
+use crate::scene::{Scene, Stroke};
+
+#[allow(unused_imports)]
+use crate::trace;
+
+/// A greyscale image, one byte per pixel from black (0) to white (255), row by row
+#[derive(Debug, Clone)]
+pub struct Raster {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Raster {
+    /// A blank white image
+    pub fn new(width: usize, height: usize) -> Self {
+        Raster {
+            width,
+            height,
+            pixels: vec![255; width * height],
+        }
+    }
+    /// Draw the lines of a scene in black, stretching its viewport over the whole image.
+    /// Other shapes are left out.
+    pub fn draw_scene(&mut self, scene: &Scene) {
+        let viewport = &scene.viewport;
+        let scale = (
+            self.width as f64 / viewport.x1,
+            self.height as f64 / viewport.y1,
+        );
+        let to_pixels = |point: Point| Point {
+            x: (point.x - viewport.x0) * scale.0,
+            y: (point.y - viewport.y0) * scale.1,
+        };
+        for (a, b, stroke) in scene.lines() {
+            let width = stroke.width * scale.0.min(scale.1);
+            self.draw_line(to_pixels(a), to_pixels(b), width);
+        }
+    }
+    /// Draw an antialiased black line with round ends, in pixel coordinates
+    fn draw_line(&mut self, a: Point, b: Point, width: f64) {
+        let radius = width / 2.0;
+        // pixels further than this from the line are untouched
+        let reach = radius + 1.0;
+        let x_range = clamp_range(a.x.min(b.x) - reach, a.x.max(b.x) + reach, self.width);
+        let y_range = clamp_range(a.y.min(b.y) - reach, a.y.max(b.y) + reach, self.height);
+        for y in y_range {
+            for x in x_range.clone() {
+                let centre = Point {
+                    x: x as f64 + 0.5,
+                    y: y as f64 + 0.5,
+                };
+                let coverage = (radius + 0.5 - distance_to_segment(centre, a, b)).clamp(0.0, 1.0);
+                let pixel = &mut self.pixels[y * self.width + x];
+                *pixel = (*pixel).min((255.0 * (1.0 - coverage)).round() as u8);
+            }
+        }
+    }
+    /// Copy another image into this one, with its top left corner at x, y
+    pub fn blit(&mut self, other: &Raster, x: usize, y: usize) {
+        for row in 0..other.height.min(self.height.saturating_sub(y)) {
+            let columns = other.width.min(self.width.saturating_sub(x));
+            let start = (y + row) * self.width + x;
+            self.pixels[start..start + columns]
+                .copy_from_slice(&other.pixels[row * other.width..row * other.width + columns]);
+        }
+    }
+}
+
+/// Render one repetition of an animation at a frame rate.
+/// Looping animations leave out the frame where they return to the start.
+pub fn render_frames(
+    animation: &Animation,
+    viewport: Viewport,
+    width: usize,
+    height: usize,
+    frame_rate: f64,
+) -> Vec<Raster> {
+    let cycle = animation.cycle();
+    let frame_interval = 1000.0 / frame_rate;
+    let frame_count = match animation.playback {
+        PlaybackMode::Once | PlaybackMode::Clamp => (cycle / frame_interval).floor() as usize + 1,
+        _ => ((cycle / frame_interval).ceil() as usize).max(1),
+    };
+    let stroke = Stroke {
+        colour: human::LINE_COLOUR.to_string(),
+        width: human::LINE_WIDTH,
+    };
+    (0..frame_count)
+        .filter_map(|i| animation.position_at(i as f64 * frame_interval))
+        .map(|joints| {
+            let mut scene = Scene::new(viewport);
+            Human::draw_joints(&joints, human::RECORDED_FACING, &stroke, &mut scene);
+            let mut frame = Raster::new(width, height);
+            frame.draw_scene(&scene);
+            frame
+        })
+        .collect()
+}
+
+/// Arrange frames of the same size in a grid, left to right then top to bottom
+pub fn sprite_sheet(frames: &[Raster], columns: usize) -> Raster {
+    let (width, height) = frames.first().map_or((0, 0), |f| (f.width, f.height));
+    let columns = columns.clamp(1, frames.len().max(1));
+    let rows = frames.len().div_ceil(columns);
+    let mut sheet = Raster::new(width * columns, height * rows);
+    for (i, frame) in frames.iter().enumerate() {
+        sheet.blit(frame, (i % columns) * width, (i / columns) * height);
+    }
+    sheet
+}
+
+/// Pixel indices from start to end, limited to the image
+fn clamp_range(start: f64, end: f64, len: usize) -> std::ops::Range<usize> {
+    let start = start.floor().max(0.0) as usize;
+    let end = (end.ceil().max(0.0) as usize).min(len);
+    start.min(end)..end
+}
+
+fn distance_to_segment(point: Point, a: Point, b: Point) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        (((point.x - a.x) * dx + (point.y - a.y) * dy) / length_squared).clamp(0.0, 1.0)
+    };
+    point.distance(&Point {
+        x: a.x + t * dx,
+        y: a.y + t * dy,
+    })
+}
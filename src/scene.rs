@@ -0,0 +1,109 @@
+use crate::camera::Viewport;
+use crate::human::Point;
+
+#[allow(unused_imports)]
+use crate::trace;
+
+/// How the outline of a shape is drawn
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stroke {
+    /// Any CSS colour
+    pub colour: String,
+    pub width: f64,
+}
+
+/// Something to draw, in viewport units
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Line {
+        a: Point,
+        b: Point,
+        stroke: Stroke,
+    },
+    Circle {
+        centre: Point,
+        radius: f64,
+        stroke: Stroke,
+        /// Any CSS colour, or none for a hollow circle
+        fill: Option<String>,
+    },
+    /// An image with its top left corner at `position`, scaled to fit the given size
+    /// while keeping its aspect ratio. With no width, the width follows from the height.
+    Image {
+        href: String,
+        position: Point,
+        width: Option<f64>,
+        height: f64,
+    },
+}
+
+/// A renderer-agnostic description of what to draw: shapes in drawing order,
+/// seen through a viewport
+pub struct Scene {
+    pub viewport: Viewport,
+    pub shapes: Vec<Shape>,
+}
+
+impl Scene {
+    /// An empty scene
+    pub fn new(viewport: Viewport) -> Self {
+        Scene {
+            viewport,
+            shapes: vec![],
+        }
+    }
+    /// Add a shape, drawn over the ones before it
+    pub fn push(&mut self, shape: Shape) {
+        self.shapes.push(shape);
+    }
+    /// Lines in the scene, in drawing order
+    pub fn lines(&self) -> impl Iterator<Item = (Point, Point, &Stroke)> {
+        self.shapes.iter().filter_map(|shape| match shape {
+            Shape::Line { a, b, stroke } => Some((*a, *b, stroke)),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+
+    fn stroke(colour: &str) -> Stroke {
+        Stroke {
+            colour: colour.to_string(),
+            width: 0.01,
+        }
+    }
+
+    #[test]
+    fn lines_skip_other_shapes_in_drawing_order() {
+        let mut scene = Scene::new(Camera::new().viewport());
+        let point = |x| Point { x, y: 0.0 };
+        scene.push(Shape::Line {
+            a: point(0.0),
+            b: point(1.0),
+            stroke: stroke("black"),
+        });
+        scene.push(Shape::Circle {
+            centre: point(0.5),
+            radius: 0.1,
+            stroke: stroke("black"),
+            fill: None,
+        });
+        scene.push(Shape::Line {
+            a: point(2.0),
+            b: point(3.0),
+            stroke: stroke("red"),
+        });
+        let lines: Vec<_> = scene.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                (point(0.0), point(1.0), &stroke("black")),
+                (point(2.0), point(3.0), &stroke("red")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use crate::animation::AnimationPosition;
+use crate::human::Point;
+use std::f64::consts::PI;
+
+#[allow(unused_imports)]
+use crate::trace;
+
+/// Lengths of each bone of the stick figure
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Skeleton {
+    pub left_thigh: f64,
+    pub left_calf: f64,
+    pub right_thigh: f64,
+    pub right_calf: f64,
+    pub torso: f64,
+    pub left_bicep: f64,
+    pub left_forearm: f64,
+    pub right_bicep: f64,
+    pub right_forearm: f64,
+    pub head: f64,
+}
+
+impl Skeleton {
+    /// Measure the bone lengths of a position
+    pub fn from_position(joints: &AnimationPosition) -> Self {
+        Skeleton {
+            left_thigh: joints.hip.distance(&joints.left_knee),
+            left_calf: joints.left_knee.distance(&joints.left_foot),
+            right_thigh: joints.hip.distance(&joints.right_knee),
+            right_calf: joints.right_knee.distance(&joints.right_foot),
+            torso: joints.hip.distance(&joints.neck),
+            left_bicep: joints.neck.distance(&joints.left_elbow),
+            left_forearm: joints.left_elbow.distance(&joints.left_hand),
+            right_bicep: joints.neck.distance(&joints.right_elbow),
+            right_forearm: joints.right_elbow.distance(&joints.right_hand),
+            head: joints.neck.distance(&joints.head),
+        }
+    }
+    /// Average bone lengths across positions
+    /// Returns none if there are no positions
+    pub fn average<'a>(positions: impl IntoIterator<Item = &'a AnimationPosition>) -> Option<Self> {
+        let mut count = 0;
+        let mut total = [0.0; 10];
+        for position in positions {
+            let lengths = Skeleton::from_position(position).lengths();
+            for (sum, length) in total.iter_mut().zip(lengths) {
+                *sum += length;
+            }
+            count += 1;
+        }
+        if count == 0 {
+            None
+        } else {
+            Some(Skeleton::from_lengths(total.map(|sum| sum / count as f64)))
+        }
+    }
+    fn lengths(&self) -> [f64; 10] {
+        [
+            self.left_thigh,
+            self.left_calf,
+            self.right_thigh,
+            self.right_calf,
+            self.torso,
+            self.left_bicep,
+            self.left_forearm,
+            self.right_bicep,
+            self.right_forearm,
+            self.head,
+        ]
+    }
+    fn from_lengths(lengths: [f64; 10]) -> Self {
+        let [left_thigh, left_calf, right_thigh, right_calf, torso, left_bicep, left_forearm, right_bicep, right_forearm, head] =
+            lengths;
+        Skeleton {
+            left_thigh,
+            left_calf,
+            right_thigh,
+            right_calf,
+            torso,
+            left_bicep,
+            left_forearm,
+            right_bicep,
+            right_forearm,
+            head,
+        }
+    }
+}
+
+/// Joint angles of the stick figure, rooted at the hip.
+/// The thighs and torso are absolute angles (radians, clockwise from the x axis),
+/// every other bone is relative to the bone it hangs from.
+#[derive(Debug, Clone)]
+pub struct Pose {
+    pub hip: Point,
+    pub left_thigh: f64,
+    pub left_calf: f64,
+    pub right_thigh: f64,
+    pub right_calf: f64,
+    pub torso: f64,
+    pub left_bicep: f64,
+    pub left_forearm: f64,
+    pub right_bicep: f64,
+    pub right_forearm: f64,
+    pub head: f64,
+}
+
+impl Pose {
+    /// Measure the joint angles of a position
+    pub fn from_position(joints: &AnimationPosition) -> Self {
+        let left_thigh = joints.hip.angle_to(&joints.left_knee);
+        let right_thigh = joints.hip.angle_to(&joints.right_knee);
+        let torso = joints.hip.angle_to(&joints.neck);
+        let left_bicep = joints.neck.angle_to(&joints.left_elbow);
+        let right_bicep = joints.neck.angle_to(&joints.right_elbow);
+        Pose {
+            hip: joints.hip,
+            left_thigh,
+            left_calf: wrap_angle(joints.left_knee.angle_to(&joints.left_foot) - left_thigh),
+            right_thigh,
+            right_calf: wrap_angle(joints.right_knee.angle_to(&joints.right_foot) - right_thigh),
+            torso,
+            left_bicep: wrap_angle(left_bicep - torso),
+            left_forearm: wrap_angle(joints.left_elbow.angle_to(&joints.left_hand) - left_bicep),
+            right_bicep: wrap_angle(right_bicep - torso),
+            right_forearm: wrap_angle(
+                joints.right_elbow.angle_to(&joints.right_hand) - right_bicep,
+            ),
+            head: wrap_angle(joints.neck.angle_to(&joints.head) - torso),
+        }
+    }
+    /// Place the joints of the pose using the bone lengths of a skeleton
+    pub fn to_position(&self, skeleton: &Skeleton) -> AnimationPosition {
+        let left_knee = self.hip.polar_offset(self.left_thigh, skeleton.left_thigh);
+        let right_knee = self
+            .hip
+            .polar_offset(self.right_thigh, skeleton.right_thigh);
+        let neck = self.hip.polar_offset(self.torso, skeleton.torso);
+        let left_bicep = self.torso + self.left_bicep;
+        let right_bicep = self.torso + self.right_bicep;
+        let left_elbow = neck.polar_offset(left_bicep, skeleton.left_bicep);
+        let right_elbow = neck.polar_offset(right_bicep, skeleton.right_bicep);
+        AnimationPosition {
+            left_foot: left_knee.polar_offset(self.left_thigh + self.left_calf, skeleton.left_calf),
+            left_knee,
+            hip: self.hip,
+            right_knee,
+            right_foot: right_knee
+                .polar_offset(self.right_thigh + self.right_calf, skeleton.right_calf),
+            neck,
+            left_elbow,
+            left_hand: left_elbow
+                .polar_offset(left_bicep + self.left_forearm, skeleton.left_forearm),
+            right_elbow,
+            right_hand: right_elbow
+                .polar_offset(right_bicep + self.right_forearm, skeleton.right_forearm),
+            head: neck.polar_offset(self.torso + self.head, skeleton.head),
+        }
+    }
+    /// Linear interpolation between two poses, turning each joint the shortest way round
+    pub fn lerp(&self, next: &Pose, offset: f64) -> Self {
+        let (angles, next_angles) = (self.angles(), next.angles());
+        Pose::from_parts(
+            self.hip.lerp(&next.hip, offset),
+            std::array::from_fn(|i| angles[i] + wrap_angle(next_angles[i] - angles[i]) * offset),
+        )
+    }
+    /// Catmull-Rom spline between two poses, shaped by the poses either side.
+    /// See [`Point::catmull_rom`].
+    pub fn catmull_rom(
+        &self,
+        previous: &Pose,
+        next: &Pose,
+        after: &Pose,
+        tangent_scale: (f64, f64),
+        offset: f64,
+    ) -> Self {
+        let angles = self.angles();
+        let (previous_angles, next_angles, after_angles) =
+            (previous.angles(), next.angles(), after.angles());
+        Pose::from_parts(
+            self.hip
+                .catmull_rom(&previous.hip, &next.hip, &after.hip, tangent_scale, offset),
+            std::array::from_fn(|i| {
+                // unwrap the neighbouring angles so the curve turns the shortest way round
+                let angle = angles[i];
+                let previous = angle - wrap_angle(angle - previous_angles[i]);
+                let next = angle + wrap_angle(next_angles[i] - angle);
+                let after = next + wrap_angle(after_angles[i] - next);
+                let m0 = (next - previous) * tangent_scale.0;
+                let m1 = (after - angle) * tangent_scale.1;
+                hermite(angle, next, m0, m1, offset)
+            }),
+        )
+    }
+    fn angles(&self) -> [f64; 10] {
+        [
+            self.left_thigh,
+            self.left_calf,
+            self.right_thigh,
+            self.right_calf,
+            self.torso,
+            self.left_bicep,
+            self.left_forearm,
+            self.right_bicep,
+            self.right_forearm,
+            self.head,
+        ]
+    }
+    fn from_parts(hip: Point, angles: [f64; 10]) -> Self {
+        let [left_thigh, left_calf, right_thigh, right_calf, torso, left_bicep, left_forearm, right_bicep, right_forearm, head] =
+            angles;
+        Pose {
+            hip,
+            left_thigh,
+            left_calf,
+            right_thigh,
+            right_calf,
+            torso,
+            left_bicep,
+            left_forearm,
+            right_bicep,
+            right_forearm,
+            head,
+        }
+    }
+}
+
+/// Bring an angle into the range -π to π
+fn wrap_angle(angle: f64) -> f64 {
+    let wrapped = (angle + PI).rem_euclid(2.0 * PI) - PI;
+    if wrapped == -PI {
+        PI
+    } else {
+        wrapped
+    }
+}
+
+/// Cubic Hermite interpolation between two values with the given tangents
+fn hermite(start: f64, end: f64, start_tangent: f64, end_tangent: f64, offset: f64) -> f64 {
+    let (t, t2, t3) = (offset, offset * offset, offset * offset * offset);
+    (2.0 * t3 - 3.0 * t2 + 1.0) * start
+        + (t3 - 2.0 * t2 + t) * start_tangent
+        + (-2.0 * t3 + 3.0 * t2) * end
+        + (t3 - t2) * end_tangent
+}
@@ -0,0 +1,107 @@
+use crate::animation::AnimationsData;
+use crate::background::{Background, Layer};
+use crate::clock::Clock;
+use crate::human::{self, Facing, Human, Point};
+use crate::scene::Scene;
+
+#[allow(unused_imports)]
+use crate::trace;
+
+/// How a figure is placed on a stage. Fields left out of a stage file take their
+/// default, which is the single walking figure the app started out with.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default)]
+pub struct FigureSettings {
+    /// Name of the animation the figure plays when no other is playing
+    pub animation: String,
+    pub position: Point,
+    pub scale: f64,
+    /// Any CSS colour
+    pub colour: String,
+    /// "left" or "right"
+    pub facing: Facing,
+    /// Milliseconds the figure's animations run ahead of the clock
+    pub time_offset: f64,
+}
+
+impl Default for FigureSettings {
+    fn default() -> Self {
+        FigureSettings {
+            animation: "walking".to_string(),
+            position: Point { x: 0.0, y: 0.0 },
+            scale: 1.0,
+            colour: human::LINE_COLOUR.to_string(),
+            facing: human::RECORDED_FACING,
+            time_offset: 0.0,
+        }
+    }
+}
+
+/// Layout of a stage file, listing the figures in drawing order
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct StageFile {
+    pub figures: Vec<FigureSettings>,
+    /// Background layers, furthest away first, replacing the default background
+    #[serde(default)]
+    pub background: Option<Vec<Layer>>,
+}
+
+/// Any number of figures sharing a scene, drawn in order in front of a background
+#[derive(Default)]
+pub struct Stage {
+    pub humans: Vec<Human>,
+    pub background: Background,
+}
+
+impl Stage {
+    /// A stage with one figure of each of the given settings
+    pub fn from_settings(figures: &[FigureSettings]) -> Self {
+        let humans = figures
+            .iter()
+            .map(|settings| {
+                let mut human = Human::new();
+                human.default_animation = settings.animation.clone();
+                human.position = settings.position;
+                human.scale = settings.scale;
+                human.colour = settings.colour.clone();
+                human.facing = settings.facing;
+                human.time_offset = settings.time_offset;
+                human
+            })
+            .collect();
+        Stage {
+            humans,
+            background: Background::new(),
+        }
+    }
+    /// A stage laid out as a stage file describes
+    pub fn from_file(file: StageFile) -> Self {
+        let mut stage = Stage::from_settings(&file.figures);
+        if let Some(layers) = file.background {
+            stage.background = Background::from_layers(layers);
+        }
+        stage
+    }
+    /// A stage with a single default figure
+    pub fn single() -> Self {
+        Stage::from_settings(&[FigureSettings::default()])
+    }
+    /// Add a figure, drawn in front of the others. Returns its index.
+    pub fn add(&mut self, human: Human) -> usize {
+        self.humans.push(human);
+        self.humans.len() - 1
+    }
+    pub fn update(&mut self, animations: &AnimationsData, clock: &Clock) {
+        for human in &mut self.humans {
+            human.update(animations, clock);
+        }
+    }
+    /// Add the background and every figure to a scene, with figures drawn a fraction
+    /// `alpha` of the way between their last two updates
+    pub fn draw(&self, scene: &mut Scene, alpha: f64) {
+        self.background.draw(scene);
+        for human in &self.humans {
+            human.draw_at(scene, alpha);
+        }
+    }
+}
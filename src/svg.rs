@@ -0,0 +1,48 @@
+use crate::scene::{Scene, Shape};
+use yew::prelude::*;
+
+#[allow(unused_imports)]
+use crate::trace;
+
+/// Render a scene as an svg element
+pub fn view(scene: &Scene, class: &str) -> Html {
+    let viewport = &scene.viewport;
+    html! {
+        <svg viewBox={format!("{} {} {} {}", viewport.x0, viewport.y0, viewport.x1, viewport.y1)} class={class.to_string()}>
+            { shapes(scene) }
+        </svg>
+    }
+}
+
+/// Render the shapes of a scene, for placing inside an svg element
+pub fn shapes(scene: &Scene) -> Html {
+    html! {
+        <>
+            { for scene.shapes.iter().map(shape) }
+        </>
+    }
+}
+
+fn shape(shape: &Shape) -> Html {
+    match shape {
+        Shape::Line { a, b, stroke } => html! {
+            <line x1={a.x.to_string()} y1={a.y.to_string()} x2={b.x.to_string()} y2={b.y.to_string()} stroke={stroke.colour.clone()} stroke-width={stroke.width.to_string()} />
+        },
+        Shape::Circle {
+            centre,
+            radius,
+            stroke,
+            fill,
+        } => html! {
+            <circle cx={centre.x.to_string()} cy={centre.y.to_string()} r={radius.to_string()} stroke={stroke.colour.clone()} stroke-width={stroke.width.to_string()} fill={fill.clone().unwrap_or_else(|| "none".to_string())} />
+        },
+        Shape::Image {
+            href,
+            position,
+            width,
+            height,
+        } => html! {
+            <image href={href.clone()} x={position.x.to_string()} y={position.y.to_string()} width={width.map(|w| w.to_string())} height={height.to_string()} />
+        },
+    }
+}
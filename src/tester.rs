@@ -1,24 +1,62 @@
-use crate::{animation, human};
+use crate::camera::Camera;
+use crate::footage::Footage;
+use crate::history::{Command, Cursor, History};
+use crate::scene::{Scene, Shape, Stroke};
+use crate::timeline::Timeline;
+use crate::{animation, export, format, human, keypoints, loader, svg};
 use wasm_bindgen::JsCast;
 use yew::prelude::*;
 
 #[allow(unused_imports)]
 use crate::trace;
 
+/// Red, green and blue of the onion skins of frames before the one being edited
+const PAST_TINT: (u8, u8, u8) = (220, 50, 50);
+/// Red, green and blue of the onion skins of frames after the one being edited
+const FUTURE_TINT: (u8, u8, u8) = (50, 100, 220);
+/// Opacity of the onion skins of the frames either side of the one being edited
+const ONION_SKIN_OPACITY: f64 = 0.5;
+
 pub enum TesterMsg {
     ClickedJoint(Joints, MouseEvent),
     MouseUp,
     MouseMove(MouseEvent),
     KeyDown(KeyboardEvent),
+    /// An animation file was picked to import
+    Import(web_sys::File),
+    /// Pose estimation keypoint files were picked to import
+    ImportKeypoints(Vec<web_sys::File>),
+    Imported(Result<Box<animation::Animation>, loader::LoadError>),
+    /// The pointer went down on a keyframe in the timeline
+    PressedKeyframe(usize, MouseEvent),
+    DraggedKeyframes(MouseEvent),
+    ReleasedKeyframes,
+    /// How many saved frames either side to show as onion skins
+    SetOnionSkins(usize),
+    /// A video or images were picked to trace over
+    LoadFootage(Vec<web_sys::File>),
+    FootageLoaded,
+    FootageSeeked,
+    SetFrameRate(f64),
 }
 
 pub struct Tester {
     human: human::Human,
     /// target and offset x and y (in pixel coordinates)
     current_joint: Option<(Joints, (f64, f64))>,
+    /// The pose when the current drag started
+    drag_start: Option<animation::AnimationPosition>,
     image_index: usize,
+    /// The saved frame being edited, or one past the last for a new frame
     editing_index: usize,
     output: animation::Animation,
+    history: History,
+    timeline: Timeline,
+    /// How many saved frames either side of the one being edited to draw faintly
+    onion_skins: usize,
+    footage: Footage,
+    /// Why the last edit could not be made, until the next one is
+    notice: Option<String>,
 }
 
 #[derive(Clone)]
@@ -36,6 +74,154 @@ pub enum Joints {
     Head,
 }
 
+impl Joints {
+    fn name(&self) -> &'static str {
+        match self {
+            Joints::LeftFoot => "left foot",
+            Joints::LeftKnee => "left knee",
+            Joints::Hip => "hip",
+            Joints::RightKnee => "right knee",
+            Joints::RightFoot => "right foot",
+            Joints::Neck => "neck",
+            Joints::LeftElbow => "left elbow",
+            Joints::LeftHand => "left hand",
+            Joints::RightElbow => "right elbow",
+            Joints::RightHand => "right hand",
+            Joints::Head => "head",
+        }
+    }
+}
+
+impl Tester {
+    /// Move the editing index, keeping it within the saved frames (or just past them)
+    /// and the reference image on the matching frame of footage
+    fn move_cursor(&mut self, index: usize) {
+        self.editing_index = index.min(self.output.keyframes.len());
+        self.image_index = self.reference_frame(self.editing_index);
+        self.timeline.select_only(self.editing_index, &self.output);
+        self.request_frames();
+    }
+    /// The frame of footage (counting from 1) a saved frame was traced from. Frames
+    /// without a recorded footage time, and the new frame past the end, follow on
+    /// from the frame before.
+    fn reference_frame(&self, index: usize) -> usize {
+        let keyframes = &self.output.keyframes;
+        let recorded = |i: usize| {
+            let time = keyframes.get(i)?.reference_time?;
+            Some(self.footage.frame_at(time)? + 1)
+        };
+        recorded(index)
+            .or_else(|| Some(recorded(index.checked_sub(1)?)? + 1))
+            .unwrap_or(index + 1)
+    }
+    /// Ask for the frames of footage on screen, being traced over and in the timeline
+    fn request_frames(&mut self) {
+        let frames: Vec<usize> = (0..self.output.keyframes.len())
+            .map(|i| self.reference_frame(i))
+            .collect();
+        self.footage.request(self.image_index - 1);
+        for frame in frames {
+            self.footage.request(frame - 1);
+        }
+    }
+    /// Time for a frame traced from a time in the footage: the same time as in the
+    /// footage, if that is known and the saved frames are timed by the footage too
+    fn reference_keyframe_time(&self, reference_time: Option<f64>) -> Option<f64> {
+        match self.output.keyframes.first() {
+            Some(first) => first.reference_time.and(reference_time),
+            None => reference_time,
+        }
+    }
+    /// Save a keyframe at its own time, in time order among the others, in place of the
+    /// keyframe at `replacing` if any. `move_cursor` is given the index it is saved at.
+    /// Nothing is saved if the time is before the start or too close to another keyframe
+    fn place(
+        &mut self,
+        keyframe: animation::Keyframe,
+        replacing: Option<usize>,
+        move_cursor: impl FnOnce(&mut Self, usize),
+    ) {
+        let time = keyframe.time;
+        let others = self.output.keyframes.iter().enumerate();
+        let others = others
+            .filter(|(i, _)| Some(*i) != replacing)
+            .map(|(_, other)| other.time);
+        let taken = others
+            .clone()
+            .any(|other| (other - time).abs() < animation::MIN_KEYFRAME_GAP);
+        if time < 0.0 || taken {
+            self.notice = Some(format!(
+                "No room for a frame at {:.0} ms, another is saved too close to it",
+                time
+            ));
+            return;
+        }
+        let index = others.filter(|&other| other < time).count();
+        let command = match replacing {
+            Some(from) => Command::MoveKeyframe {
+                from,
+                to: index,
+                before: self.output.keyframes[from].clone(),
+                after: keyframe,
+            },
+            None => Command::InsertKeyframe { index, keyframe },
+        };
+        self.perform(command, |tester| move_cursor(tester, index));
+    }
+    /// Move the editing index and load the pose of the frame there, if it is saved
+    fn select(&mut self, index: usize) {
+        self.move_cursor(index);
+        if let Some(keyframe) = self.output.keyframes.get(self.editing_index) {
+            self.human.joints = keyframe.position.clone();
+        }
+    }
+    /// Add faint, tinted copies of the saved frames either side of the one being edited,
+    /// fading further away from it
+    fn draw_onion_skins(&self, scene: &mut Scene) {
+        let keyframes = &self.output.keyframes;
+        let current = self.editing_index;
+        // furthest first, so nearer frames are drawn on top
+        for distance in (1..=self.onion_skins).rev() {
+            let opacity =
+                ONION_SKIN_OPACITY * (1.0 - (distance - 1) as f64 / self.onion_skins as f64);
+            let past = current.checked_sub(distance).and_then(|i| keyframes.get(i));
+            let future = keyframes.get(current + distance);
+            let skins = past
+                .map(|keyframe| (keyframe, PAST_TINT))
+                .into_iter()
+                .chain(future.map(|keyframe| (keyframe, FUTURE_TINT)));
+            for (keyframe, (r, g, b)) in skins {
+                let stroke = Stroke {
+                    colour: format!("rgba({}, {}, {}, {})", r, g, b, opacity),
+                    width: human::LINE_WIDTH,
+                };
+                human::Human::draw_joints(&keyframe.position, self.human.facing, &stroke, scene);
+            }
+        }
+    }
+    /// The frame being edited and the pose being traced, for the history
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            index: self.editing_index,
+            pose: self.human.joints.clone(),
+        }
+    }
+    /// Go back to a frame and pose from the history
+    fn restore(&mut self, cursor: Cursor) {
+        self.human.joints = cursor.pose;
+        self.move_cursor(cursor.index);
+    }
+    /// Make a change that can be undone, then move the cursor to where the change
+    /// leaves it
+    fn perform(&mut self, command: Command, move_cursor: impl FnOnce(&mut Self)) {
+        let before = self.cursor();
+        self.notice = None;
+        command.apply(&mut self.output);
+        move_cursor(self);
+        self.history.record(command, before, self.cursor());
+    }
+}
+
 impl Component for Tester {
     type Message = TesterMsg;
     type Properties = ();
@@ -45,13 +231,19 @@ impl Component for Tester {
         Self {
             human: human::Human::new(),
             current_joint: None,
+            drag_start: None,
             image_index: 1,
             editing_index: 0,
-            output: animation::Animation { positions: vec![] },
+            output: animation::Animation::new(),
+            history: History::new(),
+            timeline: Timeline::new(),
+            onion_skins: 2,
+            footage: Footage::new(),
+            notice: None,
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, tester_msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, tester_msg: Self::Message) -> bool {
         match tester_msg {
             TesterMsg::ClickedJoint(joint, e) => {
                 let mouse_pos = (e.client_x(), e.client_y());
@@ -82,11 +274,26 @@ impl Component for Tester {
                     new_pos.1 - target.cy().base_val().value().unwrap() as f64,
                 );
                 self.current_joint = Some((joint, offset));
+                self.drag_start = Some(self.human.joints.clone());
                 false
             }
             TesterMsg::MouseUp => {
-                self.current_joint = None;
-                false
+                let joint = self.current_joint.take();
+                match (joint, self.drag_start.take()) {
+                    // the drag already moved the joint, so only remember it
+                    (Some((joint, _)), Some(pose)) if pose != self.human.joints => {
+                        let before = Cursor {
+                            index: self.editing_index,
+                            pose,
+                        };
+                        let command = Command::MovePose {
+                            joint: joint.name(),
+                        };
+                        self.history.record(command, before, self.cursor());
+                        true
+                    }
+                    _ => false,
+                }
             }
             TesterMsg::MouseMove(e) => {
                 if let Some((joint, offset)) = &self.current_joint {
@@ -140,53 +347,312 @@ impl Component for Tester {
                 const KEY_N: u32 = 78;
                 const KEY_B: u32 = 66;
                 const KEY_D: u32 = 68;
+                const KEY_J: u32 = 74;
+                const KEY_R: u32 = 82;
+                const KEY_S: u32 = 83;
+                const KEY_I: u32 = 73;
+                const KEY_O: u32 = 79;
+                const KEY_C: u32 = 67;
+                const KEY_X: u32 = 88;
+                const KEY_Z: u32 = 90;
+                const KEY_OPEN_BRACKET: u32 = 219;
+                const KEY_CLOSE_BRACKET: u32 = 221;
                 const KEY_RIGHT: u32 = 39;
                 const KEY_LEFT: u32 = 37;
+                const KEY_UP: u32 = 38;
+                const KEY_DOWN: u32 = 40;
                 trace!("keydown: {:?}", e.key_code());
+                if e.ctrl_key() || e.meta_key() {
+                    if e.key_code() != KEY_Z {
+                        return false;
+                    }
+                    self.notice = None;
+                    let cursor = if e.shift_key() {
+                        self.history.redo(&mut self.output)
+                    } else {
+                        self.history.undo(&mut self.output)
+                    };
+                    return match cursor {
+                        Some(cursor) => {
+                            self.restore(cursor);
+                            true
+                        }
+                        None => false,
+                    };
+                }
                 let mut should_update = true;
+                let index = self.editing_index;
+                let saved = index < self.output.keyframes.len();
+                let pose = animation::AnimationPosition::from_human(&self.human);
+                let reference_time = self.footage.time(self.image_index - 1);
+                // the pose as a keyframe, at whatever time it is put in at
+                let traced = animation::Keyframe {
+                    time: 0.0,
+                    position: pose.clone(),
+                    interpolation: None,
+                    reference_time,
+                };
+                let traced_time = self.reference_keyframe_time(reference_time);
                 match e.key_code() {
+                    // overwrite the selected frame, then move on to the next
+                    KEY_N if saved => {
+                        let before = self.output.keyframes[index].clone();
+                        let after = animation::Keyframe {
+                            position: pose,
+                            reference_time,
+                            ..before.clone()
+                        };
+                        match traced_time {
+                            Some(time) => self.place(
+                                animation::Keyframe { time, ..after },
+                                Some(index),
+                                |tester, placed| tester.select(placed + 1),
+                            ),
+                            None => {
+                                let command = Command::SetKeyframe {
+                                    index,
+                                    before,
+                                    after,
+                                };
+                                self.perform(command, |tester| tester.select(index + 1));
+                            }
+                        }
+                    }
                     KEY_N => {
-                        self.output
-                            .positions
-                            .push(animation::AnimationPosition::from_human(&self.human));
-                        self.image_index += 1;
-                        self.editing_index += 1;
+                        if let Some(frame_rate) = self.footage.frame_rate() {
+                            self.output.frame_rate = Some(frame_rate);
+                        }
+                        match traced_time {
+                            Some(time) => self.place(
+                                animation::Keyframe { time, ..traced },
+                                None,
+                                |tester, placed| tester.select(placed + 1),
+                            ),
+                            None => {
+                                let command = Command::InsertKeyframe {
+                                    index: self.output.keyframes.len(),
+                                    keyframe: animation::Keyframe {
+                                        time: self.output.next_keyframe_time(),
+                                        ..traced
+                                    },
+                                };
+                                self.perform(command, |tester| tester.move_cursor(index + 1));
+                            }
+                        }
                     }
                     KEY_B => {
-                        if self.output.positions.pop().is_some()
-                            && self.image_index > 1
-                            && self.editing_index > 0
-                        {
-                            self.image_index -= 1;
-                            self.editing_index -= 1;
+                        if let Some(last) = self.output.keyframes.last() {
+                            let command = Command::RemoveKeyframe {
+                                index: self.output.keyframes.len() - 1,
+                                keyframe: last.clone(),
+                            };
+                            // step back with the frames if past the end of them
+                            self.perform(command, |tester| {
+                                tester.move_cursor(tester.editing_index)
+                            });
+                        }
+                    }
+                    // insert the pose before or after the selected frame, or when it is
+                    // in the footage if known
+                    KEY_I | KEY_O => {
+                        let at = match e.key_code() {
+                            KEY_I => index,
+                            _ => (index + 1).min(self.output.keyframes.len()),
+                        };
+                        match traced_time {
+                            Some(time) => self.place(
+                                animation::Keyframe { time, ..traced },
+                                None,
+                                |tester, placed| tester.move_cursor(placed),
+                            ),
+                            None => {
+                                let command = Command::InsertPosition {
+                                    index: at,
+                                    keyframe: traced,
+                                };
+                                self.perform(command, |tester| tester.move_cursor(at));
+                            }
                         }
                     }
+                    KEY_C if saved => {
+                        let copy = self.output.keyframes[index].clone();
+                        // a copy of a traced frame holds it for the next frame of footage
+                        let next_frame = copy
+                            .reference_time
+                            .and_then(|time| self.footage.time(self.footage.frame_at(time)? + 1));
+                        match self.reference_keyframe_time(next_frame) {
+                            Some(time) => self.place(
+                                animation::Keyframe {
+                                    time,
+                                    reference_time: next_frame,
+                                    ..copy
+                                },
+                                None,
+                                |tester, placed| tester.move_cursor(placed),
+                            ),
+                            None => {
+                                let command = Command::InsertPosition {
+                                    index: index + 1,
+                                    keyframe: copy,
+                                };
+                                self.perform(command, |tester| tester.move_cursor(index + 1));
+                            }
+                        }
+                    }
+                    KEY_X if saved => {
+                        let keyframes = &self.output.keyframes;
+                        let keyframe = keyframes[index].clone();
+                        // a traced frame leaves a gap, where the footage has no pose
+                        let command = match self.reference_keyframe_time(keyframe.reference_time) {
+                            Some(_) => Command::RemoveKeyframe { index, keyframe },
+                            None => Command::RemovePosition {
+                                index,
+                                keyframe,
+                                last_time: keyframes[keyframes.len() - 1].time,
+                            },
+                        };
+                        self.perform(command, |tester| tester.select(index));
+                    }
+                    KEY_OPEN_BRACKET if saved && index > 0 => {
+                        let command = Command::SwapPositions {
+                            a: index,
+                            b: index - 1,
+                        };
+                        self.perform(command, |tester| tester.move_cursor(index - 1));
+                    }
+                    KEY_CLOSE_BRACKET if index + 1 < self.output.keyframes.len() => {
+                        let command = Command::SwapPositions {
+                            a: index,
+                            b: index + 1,
+                        };
+                        self.perform(command, |tester| tester.move_cursor(index + 1));
+                    }
+                    KEY_UP => {
+                        if let Some(previous) = index.checked_sub(1) {
+                            self.select(previous);
+                        }
+                    }
+                    KEY_DOWN => {
+                        self.select(index + 1);
+                    }
                     KEY_D => {
-                        self.output.run_download();
+                        self.output.run_download(format::Encoding::Cbor);
+                    }
+                    KEY_J => {
+                        self.output.run_download(format::Encoding::Json);
+                    }
+                    KEY_R => {
+                        self.output.run_download(format::Encoding::Ron);
+                    }
+                    KEY_S => {
+                        let svg = export::animated_svg(&self.output);
+                        animation::download(svg.as_bytes(), "output.svg");
                     }
                     KEY_RIGHT => {
                         self.image_index += 1;
+                        self.footage.request(self.image_index - 1);
                     }
                     KEY_LEFT => {
                         if let Some(i) = (self.image_index - 1).checked_sub(1) {
                             self.image_index = i + 1;
                         }
+                        self.footage.request(self.image_index - 1);
                     }
                     _ => should_update = false,
                 }
                 should_update
             }
+            TesterMsg::Import(file) => {
+                ctx.link().send_future(async {
+                    TesterMsg::Imported(loader::read_file(file).await.map(Box::new))
+                });
+                false
+            }
+            TesterMsg::ImportKeypoints(mut files) => {
+                // OpenPose names its files by frame number
+                files.sort_by_key(|file| file.name());
+                ctx.link().send_future(async move {
+                    let mut contents = vec![];
+                    for file in files {
+                        match loader::read_bytes(&file).await {
+                            Ok(bytes) => contents.push((file.name(), bytes)),
+                            Err(e) => return TesterMsg::Imported(Err(e)),
+                        }
+                    }
+                    TesterMsg::Imported(keypoints::import_keypoints(&contents).map(Box::new))
+                });
+                false
+            }
+            TesterMsg::Imported(Ok(output)) => {
+                let command = Command::ReplaceAnimation {
+                    description: "Import",
+                    before: Box::new(self.output.clone()),
+                    after: output,
+                };
+                self.perform(command, |tester| {
+                    // carry on tracing from the last imported frame
+                    if let Some(last) = tester.output.keyframes.last() {
+                        tester.human.joints = last.position.clone();
+                    }
+                    tester.move_cursor(tester.output.keyframes.len());
+                });
+                true
+            }
+            TesterMsg::Imported(Err(e)) => {
+                log::error!("{}", e);
+                false
+            }
+            TesterMsg::PressedKeyframe(index, e) => {
+                // keep focus on the figure for the keyboard shortcuts
+                e.prevent_default();
+                if e.shift_key() || e.ctrl_key() || e.meta_key() {
+                    self.timeline.toggle(index);
+                } else if !self.timeline.selected.contains(&index) {
+                    self.select(index);
+                }
+                self.timeline.start_drag(e.client_x());
+                true
+            }
+            TesterMsg::DraggedKeyframes(e) => self.timeline.drag_to(e.client_x(), &mut self.output),
+            TesterMsg::ReleasedKeyframes => match self.timeline.end_drag() {
+                Some((indices, delta)) => {
+                    // the drag already moved the keyframes, so only remember it
+                    let command = Command::ShiftKeyframes { indices, delta };
+                    self.history.record(command, self.cursor(), self.cursor());
+                    true
+                }
+                None => false,
+            },
+            TesterMsg::SetOnionSkins(count) => {
+                self.onion_skins = count;
+                true
+            }
+            TesterMsg::LoadFootage(files) => {
+                let link = ctx.link();
+                self.footage.load(
+                    files,
+                    link.callback(|_| TesterMsg::FootageLoaded),
+                    link.callback(|_| TesterMsg::FootageSeeked),
+                );
+                self.move_cursor(self.editing_index);
+                true
+            }
+            TesterMsg::FootageLoaded => {
+                self.footage.loaded();
+                false
+            }
+            TesterMsg::FootageSeeked => self.footage.seeked(),
+            TesterMsg::SetFrameRate(frame_rate) => {
+                self.footage.set_frame_rate(frame_rate);
+                self.move_cursor(self.editing_index);
+                true
+            }
         }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
         let link = ctx.link();
-        let viewport = human::Viewport {
-            x0: 0.0,
-            y0: 0.0,
-            x1: 1.5,
-            y1: 1.0,
-        };
+        let viewport = Camera::new().viewport();
         let joint_callback =
             move |a: Joints| link.callback(move |e| TesterMsg::ClickedJoint(a.clone(), e));
         let (
@@ -215,12 +681,71 @@ impl Component for Tester {
             joint_callback(Joints::Head),
         );
 
+        let mut scene = Scene::new(viewport);
+        scene.push(Shape::Image {
+            href: self.footage.frame(self.image_index - 1).unwrap_or_default(),
+            position: human::Point { x: 0.0, y: 0.0 },
+            width: Some(viewport.x1),
+            height: viewport.y1,
+        });
+        self.draw_onion_skins(&mut scene);
+        self.human.draw(&mut scene);
+
+        let import = link.batch_callback(|e: Event| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            input
+                .files()
+                .and_then(|files| files.get(0))
+                .map(TesterMsg::Import)
+        });
+        let import_keypoints = link.batch_callback(|e: Event| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            let files = input.files()?;
+            let files = (0..files.length()).filter_map(|i| files.get(i)).collect();
+            Some(TesterMsg::ImportKeypoints(files))
+        });
+
+        let load_footage = link.batch_callback(|e: Event| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            let files = input.files()?;
+            let files = (0..files.length()).filter_map(|i| files.get(i)).collect();
+            Some(TesterMsg::LoadFootage(files))
+        });
+        let set_frame_rate = link.batch_callback(|e: Event| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            input.value().parse().ok().map(TesterMsg::SetFrameRate)
+        });
+        let set_onion_skins = link.batch_callback(|e: Event| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            input.value().parse().ok().map(TesterMsg::SetOnionSkins)
+        });
+
         html! {
             <div>
-                <p>{ format!("Image index: {}, Editing index: {}", self.image_index, self.editing_index) }</p>
+                <div class="controls">
+                    <p>{ format!("Image index: {}, Editing index: {} of {} saved", self.image_index, self.editing_index, self.output.keyframes.len()) }</p>
+                    { for self.notice.iter().map(|notice| html! { <p class="notice">{ notice }</p> }) }
+                    <label>{ "Import animation " }<input type="file" accept=".cbor,.json,.ron" onchange={import} /></label>
+                    <label>{ " Import keypoints " }<input type="file" accept=".json" multiple=true onchange={import_keypoints} /></label>
+                    { self.timeline.view(
+                        &self.output,
+                        self.editing_index,
+                        |i| self.footage.frame(self.reference_frame(i) - 1).unwrap_or_default(),
+                        link.callback(|(i, e)| TesterMsg::PressedKeyframe(i, e)),
+                        link.callback(TesterMsg::DraggedKeyframes),
+                        link.callback(|_| TesterMsg::ReleasedKeyframes),
+                    ) }
+                    <label>{ " Footage " }<input type="file" accept="video/*,image/*" multiple=true onchange={load_footage} /></label>
+                    <label>{ " Frame rate " }<input type="number" min="1" step="any" value={self.footage.frame_rate().unwrap_or_default().to_string()} onchange={set_frame_rate} /></label>
+                    <label>{ " Onion skins " }<input type="number" min="0" max="10" value={self.onion_skins.to_string()} onchange={set_onion_skins} /></label>
+                    <ol class="history">
+                        { for self.history.entries().map(|(command, undone)| html! {
+                            <li class={classes!(undone.then_some("undone"))}>{ command.describe() }</li>
+                        }) }
+                    </ol>
+                </div>
                 <svg id="svg" viewBox={format!("{} {} {} {}", viewport.x0, viewport.y0, viewport.x1, viewport.y1)} onmouseup={link.callback(|_| TesterMsg::MouseUp)} onmousemove={link.callback(TesterMsg::MouseMove)} onkeydown={link.callback(TesterMsg::KeyDown)} height="100%" tabindex="0" preserveAspectRatio="xMidYMid meet">
-                    <image href={format!("./public/output_{:0>3}.png", self.image_index)} height="1" width="1.5"/>
-                    { self.human.view()}
+                    { svg::shapes(&scene) }
                     <circle onmousedown={left_foot} cx={(self.human.joints.left_foot.x).to_string()} cy={(self.human.joints.left_foot.y).to_string()} r="0.02" stroke="aqua" stroke-width="0.01" />
                     <circle onmousedown={left_knee} cx={(self.human.joints.left_knee.x).to_string()} cy={(self.human.joints.left_knee.y).to_string()} r="0.02" stroke="aqua" stroke-width="0.01" />
                     <circle onmousedown={hip} cx={(self.human.joints.hip.x).to_string()} cy={(self.human.joints.hip.y).to_string()} r="0.02" stroke="red" stroke-width="0.01" />
@@ -0,0 +1,140 @@
+use crate::animation::{Animation, AnimationPosition};
+use crate::camera::Camera;
+use crate::human::{self, Human, Point};
+use crate::scene::{Scene, Shape, Stroke};
+use crate::svg;
+use std::collections::BTreeSet;
+use yew::prelude::*;
+
+#[allow(unused_imports)]
+use crate::trace;
+
+/// Horizontal distance on the strip for each millisecond of the animation
+const PIXELS_PER_MS: f64 = 0.3;
+
+/// A strip of keyframe thumbnails laid out by time, which can be selected and retimed
+pub struct Timeline {
+    /// Indices of the selected keyframes
+    pub selected: BTreeSet<usize>,
+    drag: Option<Drag>,
+}
+
+/// A retiming of the selected keyframes in progress
+struct Drag {
+    /// Where the pointer went down, in pixels
+    start_x: i32,
+    /// Milliseconds the keyframes have been moved so far
+    moved: f64,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Timeline {
+            selected: BTreeSet::new(),
+            drag: None,
+        }
+    }
+    /// Select one keyframe alone, or none if there is no keyframe at the index
+    pub fn select_only(&mut self, index: usize, animation: &Animation) {
+        self.selected.clear();
+        if index < animation.keyframes.len() {
+            self.selected.insert(index);
+        }
+    }
+    /// Add a keyframe to the selection, or take it out if already selected
+    pub fn toggle(&mut self, index: usize) {
+        if !self.selected.remove(&index) {
+            self.selected.insert(index);
+        }
+    }
+    /// Start moving the selected keyframes from a pointer position, if any are selected
+    pub fn start_drag(&mut self, x: i32) {
+        if self.selected.is_empty() {
+            return;
+        }
+        self.drag = Some(Drag {
+            start_x: x,
+            moved: 0.0,
+        });
+    }
+    /// Move the selected keyframes by however far the pointer is from where the drag started
+    pub fn drag_to(&mut self, x: i32, animation: &mut Animation) -> bool {
+        let drag = match &mut self.drag {
+            Some(drag) => drag,
+            None => return false,
+        };
+        let selected: Vec<usize> = self.selected.iter().copied().collect();
+        let delta = ((x - drag.start_x) as f64 / PIXELS_PER_MS).round();
+        drag.moved += animation.shift_keyframes(&selected, delta - drag.moved);
+        true
+    }
+    /// Finish moving keyframes. Returns the selected keyframes and how far they moved,
+    /// if they moved at all
+    pub fn end_drag(&mut self) -> Option<(Vec<usize>, f64)> {
+        let drag = self.drag.take()?;
+        let selected = self.selected.iter().copied().collect();
+        (drag.moved != 0.0).then_some((selected, drag.moved))
+    }
+    /// Render the strip, with the keyframe at `current` highlighted. `reference` gives
+    /// the image each keyframe was traced from, and `on_press` is told which keyframe
+    /// the pointer went down on.
+    pub fn view(
+        &self,
+        animation: &Animation,
+        current: usize,
+        reference: impl Fn(usize) -> String,
+        on_press: Callback<(usize, MouseEvent)>,
+        on_move: Callback<MouseEvent>,
+        on_release: Callback<MouseEvent>,
+    ) -> Html {
+        let width = animation.duration() * PIXELS_PER_MS;
+        let keyframes = animation.keyframes.iter().enumerate().map(|(i, keyframe)| {
+            let on_press = on_press.clone();
+            let class = classes!(
+                "keyframe",
+                (i == current).then_some("current"),
+                self.selected.contains(&i).then_some("selected")
+            );
+            html! {
+                <div class={class} style={format!("left: {}px", keyframe.time * PIXELS_PER_MS)}
+                    onmousedown={Callback::from(move |e: MouseEvent| on_press.emit((i, e)))}>
+                    { svg::view(&thumbnail(&keyframe.position, reference(i)), "thumbnail") }
+                    <span>{ format!("{:.0}", keyframe.time) }</span>
+                </div>
+            }
+        });
+        // letting go off the strip ends the drag too
+        let on_leave = on_release.clone();
+        html! {
+            <div class="timeline" onmousemove={on_move} onmouseup={on_release} onmouseleave={on_leave}>
+                <div class="track" style={format!("width: {}px", width)}>
+                    { for keyframes }
+                </div>
+            </div>
+        }
+    }
+}
+
+/// A scene of a pose drawn over the reference image it was traced from
+fn thumbnail(joints: &AnimationPosition, reference: String) -> Scene {
+    let viewport = Camera::new().viewport();
+    let mut scene = Scene::new(viewport);
+    scene.push(Shape::Image {
+        href: reference,
+        position: Point { x: 0.0, y: 0.0 },
+        width: Some(viewport.x1),
+        height: viewport.y1,
+    });
+    let stroke = Stroke {
+        colour: human::LINE_COLOUR.to_string(),
+        width: human::LINE_WIDTH,
+    };
+    Human::draw_joints(joints, human::RECORDED_FACING, &stroke, &mut scene);
+    scene
+}